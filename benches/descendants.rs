@@ -31,6 +31,18 @@ fn test_typed_descendants_mondial1(bencher: Bencher) {
     });
 }
 
+#[divan::bench]
+fn test_select_descendants_mondial1(bencher: Bencher) {
+    let xml = load_xml_data("mondial-3.0.xml");
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(&xml).unwrap();
+
+    bencher.bench_local(move || {
+        let count = xoz.select(root, "//country//religions").unwrap().len();
+        black_box(count);
+    });
+}
+
 // #[divan::bench]
 // fn test_descendants_filtered_treebank1(bencher: Bencher) {
 //     let xml = load_xml_data("treebank_e.xml");
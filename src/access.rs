@@ -29,10 +29,6 @@
 
 // all_traverse
 
-// reverse_traverse
-
-// level_order
-
 // axis
 
 // name
@@ -73,18 +69,10 @@
 
 // node_name_ref
 
-// is_prefix_defined
-
-// inherited_prefixes
-
 // prefix_for_namespace
 
-// namespace_for_prefix
-
 // unresolved_namespaces
 
-// namespaces_in_scope
-
 // value
 
 // value_type
@@ -0,0 +1,80 @@
+use sucds::{int_vectors::CompactVector, Serializable};
+use vers_vecs::{
+    trees::{bp::BpTree, Tree},
+    LevelTree, SubtreeSize,
+};
+
+// The index of each node among the children of its parent, by preorder.
+// This counts namespaces and attributes nodes as children. As this takes
+// space for every node, it's only built when a document first needs it.
+#[derive(Debug)]
+pub(crate) struct ChildIndexes {
+    indexes: CompactVector,
+}
+
+impl ChildIndexes {
+    pub(crate) fn new(tree: &BpTree) -> Self {
+        let mut indexes = vec![0; tree.size()];
+        // number the children of each node, going down the tree
+        let mut stack = tree.root().into_iter().collect::<Vec<_>>();
+        while let Some(node) = stack.pop() {
+            let mut child = tree.first_child(node);
+            let mut index = 0;
+            while let Some(current) = child {
+                indexes[tree.node_index(current)] = index;
+                index += 1;
+                stack.push(current);
+                child = tree.next_sibling(current);
+            }
+        }
+        Self {
+            // we can unwrap as we know that u64 can be converted to usize
+            indexes: CompactVector::from_slice(&indexes).unwrap(),
+        }
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        self.indexes.size_in_bytes()
+    }
+
+    // the index of node i among all its siblings
+    pub(crate) fn child_index(&self, tree: &BpTree, i: usize) -> usize {
+        self.indexes
+            .get_int(tree.node_index(i))
+            .expect("Node should exist")
+    }
+
+    // the amount of children of node i
+    pub(crate) fn child_count(&self, tree: &BpTree, i: usize) -> usize {
+        tree.last_child(i)
+            .map(|last| self.child_index(tree, last) + 1)
+            .unwrap_or(0)
+    }
+
+    // the child of node i with index n
+    pub(crate) fn nth_child(&self, tree: &BpTree, i: usize, n: usize) -> Option<usize> {
+        if n >= self.child_count(tree, i) {
+            return None;
+        }
+        // The children of i divide the preorder range of its descendants
+        // into consecutive parts, so we can binary search for the part of
+        // the child we look for. Its first node is the child itself.
+        let depth = tree.depth(i);
+        let preorder = tree.node_index(i);
+        let mut low = preorder + 1;
+        let mut high = preorder + tree.subtree_size(i).expect("Tree cannot be unbalanced");
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let node = tree.node_handle(mid);
+            let child = tree
+                .level_ancestor(node, tree.depth(node) - depth - 1)
+                .expect("Node should be a descendant");
+            if self.child_index(tree, child) < n {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Some(tree.node_handle(low))
+    }
+}
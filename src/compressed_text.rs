@@ -0,0 +1,124 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+// the uncompressed size we aim for per block. Texts are never split over
+// blocks, so a block can be bigger if it contains a big text.
+const BLOCK_SIZE: usize = 16 * 1024;
+
+// how many decompressed blocks we keep around. Text access tends to be
+// local, so a few blocks are enough to avoid decompressing the same block
+// over and over.
+const CACHED_BLOCKS: usize = 4;
+
+// Text split into blocks that are compressed individually, so that we only
+// have to decompress a single block to access a text.
+#[derive(Debug)]
+pub(crate) struct CompressedText {
+    // the offset in the uncompressed text where each block starts
+    block_starts: Vec<usize>,
+    blocks: Vec<Box<[u8]>>,
+    // the most recently used decompressed blocks by block index, the most
+    // recent last
+    cache: Mutex<Vec<(usize, Arc<str>)>>,
+}
+
+impl CompressedText {
+    // text is the concatenated text, positions the positions of the
+    // terminators in it
+    pub(crate) fn new(text: &str, positions: &[u64]) -> Self {
+        let mut block_starts = Vec::new();
+        let mut blocks = Vec::new();
+        let mut start = 0;
+        for position in positions {
+            // the block ends right after the terminator
+            let end = *position as usize + 1;
+            if end - start >= BLOCK_SIZE {
+                block_starts.push(start);
+                blocks.push(compress(&text[start..end]));
+                start = end;
+            }
+        }
+        if start < text.len() {
+            block_starts.push(start);
+            blocks.push(compress(&text[start..]));
+        }
+        Self {
+            block_starts,
+            blocks,
+            cache: Mutex::new(Vec::with_capacity(CACHED_BLOCKS)),
+        }
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        self.block_starts.len() * std::mem::size_of::<usize>()
+            + self.blocks.len() * std::mem::size_of::<Box<[u8]>>()
+            + self.blocks.iter().map(|b| b.len()).sum::<usize>()
+            + self
+                .cache
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_, text)| text.len())
+                .sum::<usize>()
+    }
+
+    fn block_index(&self, index: usize) -> usize {
+        self.block_starts
+            .partition_point(|start| *start <= index)
+            .saturating_sub(1)
+    }
+
+    // the decompressed text of a block, from the cache if we can
+    fn block(&self, block_index: usize) -> Arc<str> {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(i) = cache.iter().position(|(index, _)| *index == block_index) {
+            let entry = cache.remove(i);
+            let text = entry.1.clone();
+            cache.push(entry);
+            return text;
+        }
+        let bytes = lz4_flex::decompress_size_prepended(&self.blocks[block_index])
+            .expect("Block should decompress");
+        let text: Arc<str> = String::from_utf8(bytes)
+            .expect("Block should be utf8")
+            .into();
+        if cache.len() == CACHED_BLOCKS {
+            cache.remove(0);
+        }
+        cache.push((block_index, text.clone()));
+        text
+    }
+
+    // get text in range. The range may not cross a terminator
+    pub(crate) fn get(&self, range: Range<usize>) -> String {
+        let block_index = self.block_index(range.start);
+        let block_start = self.block_starts[block_index];
+        self.block(block_index)[range.start - block_start..range.end - block_start].to_string()
+    }
+
+    // get the text in range as a sequence of slices, one for each block
+    // the range overlaps
+    pub(crate) fn chunks(&self, range: Range<usize>) -> impl Iterator<Item = String> + '_ {
+        let first = self.block_index(range.start);
+        let last = self
+            .block_starts
+            .partition_point(|start| *start < range.end);
+        (first..last).map(move |block_index| {
+            let block_start = self.block_starts[block_index];
+            let text = self.block(block_index);
+            let start = range.start.saturating_sub(block_start);
+            let end = (range.end - block_start).min(text.len());
+            text[start..end].to_string()
+        })
+    }
+
+    pub(crate) fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+fn compress(text: &str) -> Box<[u8]> {
+    lz4_flex::compress_prepend_size(text.as_bytes()).into_boxed_slice()
+}
@@ -1,4 +1,6 @@
-use crate::{iter::AttributesIter, NodeName, NodeType};
+use std::borrow::Cow;
+
+use crate::{iter::AttributesIter, text::borrowed, NodeName, NodeType};
 
 use super::{Document, Node};
 
@@ -36,6 +38,15 @@ impl Document {
     }
 
     pub fn attribute_value<'a>(&self, node: Node, name: impl Into<NodeName<'a>>) -> Option<&str> {
+        self.attribute_text(node, name).map(borrowed)
+    }
+
+    // the value of an attribute, for any text storage
+    pub(crate) fn attribute_text<'a>(
+        &self,
+        node: Node,
+        name: impl Into<NodeName<'a>>,
+    ) -> Option<Cow<'_, str>> {
         let attribute_node = self.attribute_node(node, name)?;
         let text_id = self.structure.text_id(attribute_node.get());
         Some(self.text_usage.text_value(text_id))
@@ -44,7 +55,16 @@ impl Document {
     pub fn attribute_entries(
         &self,
         node: Node,
-    ) -> impl Iterator<Item = (&NodeName, &str)> + use<'_> {
+    ) -> impl Iterator<Item = (&NodeName<'_>, &str)> + use<'_> {
+        self.attribute_texts(node)
+            .map(|(name, value)| (name, borrowed(value)))
+    }
+
+    // the names and values of attributes, for any text storage
+    pub(crate) fn attribute_texts(
+        &self,
+        node: Node,
+    ) -> impl Iterator<Item = (&NodeName<'_>, Cow<'_, str>)> + use<'_> {
         AttributesIter::new(self, node).map(move |n| {
             let text_id = self.structure.text_id(n.get());
             let value = self.text_usage.text_value(text_id);
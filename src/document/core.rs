@@ -1,13 +1,14 @@
-use std::num::NonZeroI64;
+use std::{num::NonZeroI64, sync::OnceLock};
 
-use vers_vecs::trees::Tree;
+use vers_vecs::{trees::Tree, LevelTree};
 
 use crate::{
-    iter::NextSiblingIter, node_info_vec::SArrayMatrix, serializer::serialize_node_to_string,
-    structure::Structure, text::TextUsage,
+    child_indexes::ChildIndexes, iter::NextSiblingIter, node_info_vec::SArrayMatrix,
+    serializer::serialize_node_to_string, structure::Structure, text::TextUsage,
+    text_offsets::TextOffsets, word_index::WordIndex,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 // we start counting at 1 so the Option of a Node is the same size as a Node
 pub(crate) struct DocumentId(NonZeroI64);
 
@@ -25,9 +26,16 @@ pub(crate) struct Document {
     pub(crate) id: DocumentId,
     pub(crate) structure: Structure<SArrayMatrix>,
     pub(crate) text_usage: TextUsage,
+    // built on first use by a word search
+    pub(crate) word_index: OnceLock<WordIndex>,
+    // built on first use of a text offset
+    pub(crate) text_offsets: OnceLock<TextOffsets>,
+    // built on first use of a child index
+    pub(crate) child_indexes: OnceLock<ChildIndexes>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// ordered by tree position, which is also preorder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) struct Node(usize);
 
 impl Node {
@@ -47,7 +55,11 @@ impl Document {
     }
 
     pub(crate) fn heap_size(&self) -> usize {
-        self.structure.heap_size() + self.text_usage.heap_size()
+        self.structure.heap_size()
+            + self.text_usage.heap_size()
+            + self.word_index.get().map(|w| w.heap_size()).unwrap_or(0)
+            + self.text_offsets.get().map(|t| t.heap_size()).unwrap_or(0)
+            + self.child_indexes.get().map(|c| c.heap_size()).unwrap_or(0)
     }
 
     pub(crate) fn primitive_parent(&self, node: Node) -> Option<Node> {
@@ -68,6 +80,28 @@ impl Document {
 
     // next_sibling is itself already primitive in behavior
 
+    // the position of the closing paren of the node; the nodes in its
+    // subtree are the ones between the node and this
+    pub(crate) fn primitive_close(&self, node: Node) -> usize {
+        self.structure
+            .tree()
+            .close(node.0)
+            .expect("node has a closing paren")
+    }
+
+    // the ancestor-or-self of the node that is a sibling of the other node,
+    // which must be in the forest of that sibling
+    pub(crate) fn primitive_sibling_ancestor(&self, node: Node, sibling: Node) -> Node {
+        let tree = self.structure.tree();
+        let levels = tree.depth(node.0) - tree.depth(sibling.0);
+        if levels == 0 {
+            return node;
+        }
+        tree.level_ancestor(node.0, levels)
+            .map(Node)
+            .expect("node should be in the forest of the sibling")
+    }
+
     pub(crate) fn primitive_children(&self, node: Node) -> impl Iterator<Item = Node> + use<'_> {
         NextSiblingIter::new(self, self.primitive_first_child(node))
     }
@@ -15,6 +15,14 @@ impl Document {
         self.structure.lookup_node_info_id_for_node_type(node_type)
     }
 
+    pub(crate) fn node_info_ids(&self, matches: impl Fn(&NodeType) -> bool) -> Vec<NodeInfoId> {
+        self.structure.lookup_node_info_ids(matches)
+    }
+
+    pub(crate) fn node_type_for_node_info_id(&self, node_info_id: NodeInfoId) -> &NodeType<'_> {
+        self.structure.lookup_node_info(node_info_id).node_type()
+    }
+
     pub(crate) fn node_info_id_for_node(&self, node: Node) -> NodeInfoId {
         self.structure.node_info_id(node.get())
     }
@@ -23,6 +31,37 @@ impl Document {
         self.structure.tree().node_index(node.get())
     }
 
+    pub fn postorder(&self, node: Node) -> usize {
+        // the nodes that close before this one are its descendants and the
+        // nodes before it that aren't its ancestors
+        let depth = self.structure.tree().depth(node.get()) as usize;
+        self.preorder(node) + self.structure.subtree_size(node.get()) - 1 - depth
+    }
+
+    pub fn node_at_preorder(&self, preorder: usize) -> Option<Node> {
+        let tree = self.structure.tree();
+        if preorder >= tree.size() {
+            return None;
+        }
+        self.visible_node(tree.node_handle(preorder))
+    }
+
+    // the node at a position in the tree, if a node starts there and it's
+    // not a namespaces or attributes node
+    pub(crate) fn visible_node(&self, position: usize) -> Option<Node> {
+        if position >= self.structure.tree().size() * 2
+            || !self.structure.get_node_info(position).is_open_tag()
+        {
+            return None;
+        }
+        let node = Node::new(position);
+        if self.node_info_id_for_node(node).is_special() {
+            None
+        } else {
+            Some(node)
+        }
+    }
+
     pub fn node_name(&self, node: Node) -> Option<&NodeName> {
         match self.node_type(node) {
             NodeType::Element(node_name) => Some(node_name),
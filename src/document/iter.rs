@@ -1,10 +1,11 @@
 use crate::{
     iter::{
-        AncestorIter, AttributesIter, ChildrenIter, DescendantsIter, FollowingIter,
-        NextSiblingIter, PreviousSiblingIter, TypedDescendantsIter, TypedFollowingIter,
-        WithSelfIter, WithTypedSelfIter,
+        AncestorIter, AttributesIter, ChildrenIter, DescendantsIter, FollowingIter, LevelOrderIter,
+        NextSiblingIter, PreviousSiblingIter, TypedDescendantsAnyIter, TypedDescendantsIter,
+        TypedFollowingIter, TypedFollowingSiblingIter, WithSelfIter, WithTypedSelfIter,
     },
-    traverse::TraverseIter,
+    node_info_vec::NodeInfoId,
+    traverse::{ReverseTraverseIter, TraverseIter},
     NodeType, TraverseState,
 };
 
@@ -46,7 +47,7 @@ impl Document {
         ancestors.into_iter().rev()
     }
 
-    pub fn descendants(&self, node: Node) -> impl Iterator<Item = Node> + use<'_> {
+    pub fn descendants(&self, node: Node) -> impl DoubleEndedIterator<Item = Node> + use<'_> {
         DescendantsIter::new(self, node)
     }
 
@@ -98,6 +99,43 @@ impl Document {
         }
     }
 
+    pub fn typed_descendants_any(
+        &self,
+        node: Node,
+        node_types: &[NodeType],
+    ) -> impl Iterator<Item = Node> + use<'_> {
+        let mut node_info_ids = node_types
+            .iter()
+            .filter_map(|node_type| self.node_info_id(node_type.clone()))
+            .collect::<Vec<_>>();
+        // the same node type given twice shouldn't give nodes twice
+        node_info_ids.sort();
+        node_info_ids.dedup();
+        TypedDescendantsAnyIter::new(self, node, node_info_ids)
+    }
+
+    pub fn typed_descendants_namespace(
+        &self,
+        node: Node,
+        namespace: &str,
+    ) -> impl Iterator<Item = Node> + use<'_> {
+        let node_info_ids = self.structure.lookup_node_info_ids(|node_type| {
+            matches!(node_type, NodeType::Element(name) if name.namespace() == namespace.as_bytes())
+        });
+        TypedDescendantsAnyIter::new(self, node, node_info_ids)
+    }
+
+    pub fn typed_descendants_local_name(
+        &self,
+        node: Node,
+        local_name: &str,
+    ) -> impl Iterator<Item = Node> + use<'_> {
+        let node_info_ids = self.structure.lookup_node_info_ids(|node_type| {
+            matches!(node_type, NodeType::Element(name) if name.local_name() == local_name.as_bytes())
+        });
+        TypedDescendantsAnyIter::new(self, node, node_info_ids)
+    }
+
     pub fn typed_following(
         &self,
         node: Node,
@@ -106,10 +144,44 @@ impl Document {
         TypedFollowingIter::new(self, node, node_type)
     }
 
+    pub fn typed_children(
+        &self,
+        node: Node,
+        node_type: NodeType,
+    ) -> impl Iterator<Item = Node> + use<'_> {
+        let node_info_id = self.node_info_id(node_type);
+        let first = node_info_id.and_then(|id| self.typed_child_by_node_info_id(node, id));
+        // if the node type doesn't exist, first is None and the dummy node
+        // info id is never used
+        TypedFollowingSiblingIter::new(self, first, node_info_id.unwrap_or(NodeInfoId::new(0)))
+    }
+
+    pub fn typed_following_siblings(
+        &self,
+        node: Node,
+        node_type: NodeType,
+    ) -> impl Iterator<Item = Node> + use<'_> {
+        let node_info_id = self.node_info_id(node_type);
+        let first =
+            node_info_id.and_then(|id| self.typed_following_sibling_by_node_info_id(node, id));
+        TypedFollowingSiblingIter::new(self, first, node_info_id.unwrap_or(NodeInfoId::new(0)))
+    }
+
     pub fn traverse(
         &self,
         node: Node,
     ) -> impl Iterator<Item = (&NodeType, TraverseState, Node)> + use<'_> {
         TraverseIter::new(self, node)
     }
+
+    pub fn reverse_traverse(
+        &self,
+        node: Node,
+    ) -> impl Iterator<Item = (&NodeType<'_>, TraverseState, Node)> + use<'_> {
+        ReverseTraverseIter::new(self, node)
+    }
+
+    pub fn level_order(&self, node: Node) -> impl Iterator<Item = Node> + use<'_> {
+        LevelOrderIter::new(self, node)
+    }
 }
@@ -4,6 +4,7 @@ mod info;
 mod iter;
 mod nav;
 mod ns;
+mod search;
 mod str;
 
 pub(crate) use core::DocumentId;
@@ -1,6 +1,6 @@
-use vers_vecs::{trees::Tree, IsAncestor};
+use vers_vecs::{trees::Tree, IsAncestor, LevelTree};
 
-use crate::{node_info_vec::NodeInfoId, NodeType};
+use crate::{child_indexes::ChildIndexes, node_info_vec::NodeInfoId, NodeType};
 
 use super::{Document, Node};
 
@@ -100,6 +100,71 @@ impl Document {
             .expect("Illegal tree structure or node not in tree")
     }
 
+    // attribute and namespace nodes are under a hidden node, which is an
+    // extra level in the tree that doesn't count as a parent
+    fn is_under_special(&self, node: Node) -> bool {
+        self.primitive_parent(node)
+            .is_some_and(|parent| self.node_info_id_for_node(parent).is_special())
+    }
+
+    pub fn depth(&self, node: Node) -> usize {
+        let depth = self.structure.tree().depth(node.get()) as usize;
+        if self.is_under_special(node) {
+            depth - 1
+        } else {
+            depth
+        }
+    }
+
+    pub fn level_ancestor(&self, node: Node, levels: usize) -> Option<Node> {
+        if levels == 0 {
+            return Some(node);
+        }
+        let levels = if self.is_under_special(node) {
+            levels + 1
+        } else {
+            levels
+        };
+        self.structure
+            .tree()
+            .level_ancestor(node.get(), levels as u64)
+            .map(Node::new)
+    }
+
+    pub fn lowest_common_ancestor(&self, a: Node, b: Node) -> Node {
+        let (depth_a, depth_b) = (self.depth(a), self.depth(b));
+        // bring the deepest node up to the same depth as the other
+        let (a, b, depth) = if depth_a > depth_b {
+            (
+                self.level_ancestor(a, depth_a - depth_b).unwrap(),
+                b,
+                depth_b,
+            )
+        } else {
+            (
+                a,
+                self.level_ancestor(b, depth_b - depth_a).unwrap(),
+                depth_a,
+            )
+        };
+        if a == b {
+            return a;
+        }
+        // the ancestors at the same level are different up to the lowest
+        // common ancestor and the same from there, so we can do a binary
+        // search. At level depth, we're at the root.
+        let (mut low, mut high) = (0, depth);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.level_ancestor(a, mid) == self.level_ancestor(b, mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        self.level_ancestor(a, high).unwrap()
+    }
+
     pub fn top_element(&self, node: Node) -> Node {
         if self.is_document(node) {
             return self.document_element();
@@ -121,13 +186,45 @@ impl Document {
         self.is_element(node) && self.is_directly_under_document(node)
     }
 
+    // the amount of namespaces and attributes nodes among the children.
+    // These always come first.
+    fn special_child_count(&self, node: Node) -> usize {
+        self.primitive_children(node)
+            .take(2)
+            .take_while(|child| self.node_info_id_for_node(*child).is_special())
+            .count()
+    }
+
+    fn child_indexes(&self) -> &ChildIndexes {
+        self.child_indexes
+            .get_or_init(|| ChildIndexes::new(self.structure.tree()))
+    }
+
     pub fn child_index(&self, parent: Node, node: Node) -> Option<usize> {
-        for (i, child) in self.children(parent).enumerate() {
-            if child == node {
-                return Some(i);
-            }
+        if self.primitive_parent(node) != Some(parent) || self.is_under_special(node) {
+            return None;
         }
-        None
+        let index = self
+            .child_indexes()
+            .child_index(self.structure.tree(), node.get());
+        // namespaces and attributes nodes aren't children themselves
+        index.checked_sub(self.special_child_count(parent))
+    }
+
+    pub fn child_count(&self, node: Node) -> usize {
+        self.child_indexes()
+            .child_count(self.structure.tree(), node.get())
+            - self.special_child_count(node)
+    }
+
+    pub fn nth_child(&self, node: Node, n: usize) -> Option<Node> {
+        self.child_indexes()
+            .nth_child(
+                self.structure.tree(),
+                node.get(),
+                n + self.special_child_count(node),
+            )
+            .map(Node::new)
     }
 
     pub fn typed_descendant(&self, node: Node, node_type: NodeType) -> Option<Node> {
@@ -159,4 +256,121 @@ impl Document {
             .typed_following(node.get(), node_info_id)
             .map(Node::new)
     }
+
+    // the first node with the node info id after the node in document
+    // order, whether in its subtree or not
+    pub(crate) fn typed_next_by_node_info_id(
+        &self,
+        node: Node,
+        node_info_id: NodeInfoId,
+    ) -> Option<Node> {
+        self.structure
+            .typed_next(node.get(), node_info_id)
+            .map(Node::new)
+    }
+
+    pub(crate) fn typed_child_by_node_info_id(
+        &self,
+        node: Node,
+        node_info_id: NodeInfoId,
+    ) -> Option<Node> {
+        self.structure
+            .typed_child(node.get(), node_info_id)
+            .map(Node::new)
+    }
+
+    pub(crate) fn typed_following_sibling_by_node_info_id(
+        &self,
+        node: Node,
+        node_info_id: NodeInfoId,
+    ) -> Option<Node> {
+        self.structure
+            .typed_following_sibling(node.get(), node_info_id)
+            .map(Node::new)
+    }
+
+    pub fn typed_preceding(&self, node: Node, node_type: NodeType) -> Option<Node> {
+        let node_info_id = self.node_info_id(node_type)?;
+        self.typed_preceding_by_node_info_id(node, node_info_id)
+    }
+
+    pub(crate) fn typed_preceding_by_node_info_id(
+        &self,
+        node: Node,
+        node_info_id: NodeInfoId,
+    ) -> Option<Node> {
+        self.structure
+            .typed_preceding(node.get(), node_info_id)
+            .map(Node::new)
+    }
+
+    pub fn typed_preceding_sibling(&self, node: Node, node_type: NodeType) -> Option<Node> {
+        let node_info_id = self.node_info_id(node_type)?;
+        self.typed_preceding_sibling_by_node_info_id(node, node_info_id)
+    }
+
+    pub(crate) fn typed_preceding_sibling_by_node_info_id(
+        &self,
+        node: Node,
+        node_info_id: NodeInfoId,
+    ) -> Option<Node> {
+        self.structure
+            .typed_preceding_sibling(node.get(), node_info_id)
+            .map(Node::new)
+    }
+
+    // The position of node among the children of its parent with the same
+    // node info id, from 1. We count by rank from the first child of the
+    // parent; occurrences nested in a preceding sibling are skipped by
+    // continuing the rank after the end of that sibling.
+    pub(crate) fn typed_position(&self, node: Node) -> usize {
+        let node_info_id = self.node_info_id_for_node(node);
+        let parent = self
+            .primitive_parent(node)
+            .expect("node should have parent");
+        let first = self
+            .primitive_first_child(parent)
+            .expect("parent should have children");
+        let rank = |i: usize| {
+            self.structure
+                .rank_node_info_id(i, node_info_id)
+                .expect("position should be in the tree")
+        };
+        let end = rank(node.get());
+        let mut current = rank(first.get());
+        let mut position = 1;
+        while current < end {
+            let found = self
+                .structure
+                .select_node_info_id(current, node_info_id)
+                .map(Node::new)
+                .expect("rank should be selectable");
+            let sibling = self.primitive_sibling_ancestor(found, node);
+            if sibling == found {
+                position += 1;
+            }
+            let close = self
+                .structure
+                .tree()
+                .close(sibling.get())
+                .expect("Tree cannot be unbalanced");
+            current = rank(close);
+        }
+        position
+    }
+
+    pub fn typed_ancestor(&self, node: Node, node_type: NodeType) -> Option<Node> {
+        let node_info_id = self.node_info_id(node_type)?;
+        self.typed_ancestor_by_node_info_id(node, node_info_id)
+    }
+
+    pub(crate) fn typed_ancestor_by_node_info_id(
+        &self,
+        node: Node,
+        node_info_id: NodeInfoId,
+    ) -> Option<Node> {
+        self.structure
+            .typed_ancestor(node.get(), node_info_id)
+            .map(Node::new)
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{iter::NamespacesIter, NodeType};
+use crate::{error::QNameError, iter::NamespacesIter, NodeName, NodeType};
 
 use super::{Document, Node};
 
@@ -52,6 +52,78 @@ impl Document {
         }
     }
 
+    pub fn namespace_for_prefix(&self, node: Node, prefix: &[u8]) -> Option<&[u8]> {
+        for ancestor in self.ancestors_or_self(node) {
+            for (declared_prefix, uri) in self.namespace_entries(ancestor) {
+                if declared_prefix == prefix {
+                    return Some(uri);
+                }
+            }
+        }
+        if prefix.is_empty() {
+            Some(b"")
+        } else if prefix == b"xml" {
+            Some(XML_NAMESPACE)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_prefix_defined(&self, node: Node, prefix: &[u8]) -> bool {
+        self.namespace_for_prefix(node, prefix).is_some()
+    }
+
+    pub fn namespaces_in_scope(&self, node: Node) -> Vec<(&[u8], &[u8])> {
+        // the nearest declaration of a prefix shadows those further up
+        let mut in_scope: Vec<(&[u8], &[u8])> = Vec::new();
+        for ancestor in self.ancestors_or_self(node) {
+            for (prefix, uri) in self.namespace_entries(ancestor) {
+                if !in_scope.iter().any(|(p, _)| *p == prefix) {
+                    in_scope.push((prefix, uri));
+                }
+            }
+        }
+        // xmlns="" undeclares the default namespace
+        in_scope.retain(|(_, uri)| !uri.is_empty());
+        in_scope.push((b"xml", XML_NAMESPACE));
+        in_scope
+    }
+
+    pub fn inherited_prefixes(&self, node: Node) -> Vec<(&[u8], &[u8])> {
+        let Some(parent) = self.parent(node) else {
+            return Vec::new();
+        };
+        let declared = self
+            .namespace_entries(node)
+            .map(|(prefix, _)| prefix)
+            .collect::<Vec<_>>();
+        self.namespaces_in_scope(parent)
+            .into_iter()
+            .filter(|(prefix, _)| *prefix != b"xml" && !declared.contains(prefix))
+            .collect()
+    }
+
+    pub fn resolve_qname<'a>(
+        &'a self,
+        node: Node,
+        qname: &'a str,
+    ) -> Result<NodeName<'a>, QNameError> {
+        // QName-valued content may have surrounding whitespace
+        let trimmed = qname.trim();
+        let (prefix, local_name) = match trimmed.split_once(':') {
+            Some((prefix, local_name)) if is_ncname(prefix) => (prefix, local_name),
+            Some(_) => return Err(QNameError::Invalid(qname.to_string())),
+            None => ("", trimmed),
+        };
+        if !is_ncname(local_name) {
+            return Err(QNameError::Invalid(qname.to_string()));
+        }
+        let namespace = self
+            .namespace_for_prefix(node, prefix.as_bytes())
+            .ok_or_else(|| QNameError::UndeclaredPrefix(prefix.to_string()))?;
+        Ok(NodeName::from_bytes(namespace, local_name.as_bytes()))
+    }
+
     pub fn node_prefix(&self, node: Node) -> Option<&[u8]> {
         let name = self.node_name(node)?;
         self.prefix_for_namespace(node, name.namespace())
@@ -71,3 +143,13 @@ impl Document {
         }
     }
 }
+
+// a name without a colon, as used for prefixes and local names
+fn is_ncname(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '\u{B7}'))
+}
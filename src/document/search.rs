@@ -0,0 +1,210 @@
+use std::{borrow::Cow, ops::Range};
+
+use regex::Regex;
+
+use crate::{
+    error::XPathError,
+    mta::Nodes,
+    mta_compiler::compile,
+    text::TextId,
+    word_index::{Posting, WordIndex},
+    xpath, NodeType, WordQuery,
+};
+
+use super::{Document, Node};
+
+impl Document {
+    pub fn select(&self, node: Node, expr: &str) -> Result<Nodes, XPathError> {
+        // prefixes are resolved in the scope of the context node, or of the
+        // document element if that's the document node
+        let scope = if self.is_document(node) {
+            self.document_element()
+        } else {
+            node
+        };
+        let resolve_prefix = |prefix: &str| {
+            self.namespace_for_prefix(scope, prefix.as_bytes())
+                .map(|uri| String::from_utf8_lossy(uri).into_owned())
+        };
+        let mut selected = Nodes::new();
+        for core in xpath::parse(expr, &resolve_prefix)? {
+            selected.append(compile(&core, self)?.run(self, node));
+        }
+        Ok(selected)
+    }
+
+    pub fn search_text_regex<'a>(
+        &'a self,
+        node: Node,
+        regex: &'a Regex,
+    ) -> impl Iterator<Item = (Node, Range<usize>)> + use<'a> {
+        let text_ids = self.structure.text_ids(node.get());
+        self.text_usage
+            .texts(text_ids)
+            .filter_map(move |(text_id, text)| {
+                let text_node = Node::new(self.structure.text_index(text_id));
+                // comments and processing instructions are text content too,
+                // but we don't search them
+                if self.is_text(text_node) || self.is_attribute(text_node) {
+                    Some((text_node, text))
+                } else {
+                    None
+                }
+            })
+            .flat_map(move |(text_node, text)| {
+                regex
+                    .find_iter(&text)
+                    .map(|found| (text_node, found.range()))
+                    .collect::<Vec<_>>()
+            })
+    }
+
+    fn word_index(&self) -> &WordIndex {
+        self.word_index.get_or_init(|| {
+            let text_ids = self.structure.text_ids(self.root().get());
+            WordIndex::new(self.text_node_texts(text_ids))
+        })
+    }
+
+    // the nearest ancestor of a node for which is_block is true, or the root
+    // if there is none
+    fn block(&self, node: Node, is_block: impl Fn(&NodeType) -> bool) -> Node {
+        self.ancestors(node)
+            .find(|n| is_block(self.node_type(*n)))
+            .unwrap_or(self.root())
+    }
+
+    // A match can go on from one text node into the next if they are in the
+    // same block, whatever inline markup is in between.
+    fn same_block<F: Fn(&NodeType) -> bool>(
+        &self,
+        is_block: F,
+    ) -> impl Fn(&Posting, &Posting) -> bool + use<'_, F> {
+        move |a, b| {
+            a.text_id == b.text_id
+                || self.block(self.posting_node(a), &is_block)
+                    == self.block(self.posting_node(b), &is_block)
+        }
+    }
+
+    fn posting_node(&self, posting: &Posting) -> Node {
+        Node::new(self.structure.text_index(posting.text_id))
+    }
+
+    pub fn search_words(
+        &self,
+        node: Node,
+        query: &WordQuery,
+        is_block: impl Fn(&NodeType) -> bool,
+    ) -> Vec<(Node, usize)> {
+        let mut found = self
+            .word_index()
+            .matches(query, self.same_block(is_block))
+            .iter()
+            .filter(|(first, last)| {
+                self.is_ancestor_or_self(node, self.posting_node(first))
+                    && self.is_ancestor_or_self(node, self.posting_node(last))
+            })
+            .map(|(first, _)| (self.posting_node(first), first.offset))
+            .collect::<Vec<_>>();
+        // a near query can have multiple matches starting at the same word
+        found.dedup();
+        found
+    }
+
+    pub fn search_words_in(
+        &self,
+        node: Node,
+        query: &WordQuery,
+        node_type: &NodeType,
+        is_block: impl Fn(&NodeType) -> bool,
+    ) -> Vec<Node> {
+        let mut found = self
+            .word_index()
+            .matches(query, self.same_block(is_block))
+            .iter()
+            .filter_map(|(first, last)| {
+                let last = self.posting_node(last);
+                self.ancestors(self.posting_node(first))
+                    .filter(|ancestor| self.node_type(*ancestor) == node_type)
+                    .find(|ancestor| self.is_ancestor(*ancestor, last))
+            })
+            .filter(|ancestor| self.is_ancestor_or_self(node, *ancestor))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|n| n.get());
+        found.dedup();
+        found
+    }
+
+    pub fn snippet_context(
+        &self,
+        node: Node,
+        range: Range<usize>,
+        before: usize,
+        after: usize,
+        is_block: impl Fn(&NodeType) -> bool,
+    ) -> Option<(String, String)> {
+        let text = self.node_text(node)?;
+        // this also checks that the range is in bounds and on char boundaries
+        text.get(range.clone())?;
+        let (own_before, own_after) = (&text[..range.start], &text[range.end..]);
+        if !self.is_text(node) {
+            // attribute values are not part of the text flow
+            return Some((
+                last_chars(std::iter::once(Cow::Borrowed(own_before)), before),
+                first_chars(std::iter::once(Cow::Borrowed(own_after)), after),
+            ));
+        }
+        let block = self.block(node, is_block);
+        let block_text_ids = self.structure.text_ids(block.get());
+        let text_id = self.structure.text_id(node.get()).id();
+        // go backwards one text at a time, so we only look at as many texts
+        // as we need
+        let texts_before = (block_text_ids.start..text_id)
+            .rev()
+            .map(TextId::new)
+            .filter(|text_id| self.is_text(Node::new(self.structure.text_index(*text_id))))
+            .map(|text_id| self.text_usage.text_value(text_id));
+        let texts_after = self
+            .text_node_texts(text_id + 1..block_text_ids.end)
+            .map(|(_, text)| text);
+        Some((
+            last_chars(
+                std::iter::once(Cow::Borrowed(own_before)).chain(texts_before),
+                before,
+            ),
+            first_chars(
+                std::iter::once(Cow::Borrowed(own_after)).chain(texts_after),
+                after,
+            ),
+        ))
+    }
+}
+
+// the last amount of characters of texts, which are given last one first
+fn last_chars<'a>(texts: impl Iterator<Item = Cow<'a, str>>, amount: usize) -> String {
+    let mut chars = Vec::new();
+    for text in texts {
+        if chars.len() == amount {
+            break;
+        }
+        chars.extend(text.chars().rev().take(amount - chars.len()));
+    }
+    chars.reverse();
+    chars.into_iter().collect()
+}
+
+fn first_chars<'a>(texts: impl Iterator<Item = Cow<'a, str>>, amount: usize) -> String {
+    let mut chars = String::new();
+    let mut count = 0;
+    for text in texts {
+        if count == amount {
+            break;
+        }
+        for c in text.chars().take(amount - count) {
+            chars.push(c);
+            count += 1;
+        }
+    }
+    chars
+}
@@ -1,6 +1,12 @@
+use std::{borrow::Cow, fmt, ops::Range};
+
 use quick_xml::events::BytesPI;
 
-use crate::NodeType;
+use crate::{
+    text::{borrowed, TextId, ValueId},
+    text_offsets::TextOffsets,
+    NodeType,
+};
 
 use super::{Document, Node};
 
@@ -37,9 +43,9 @@ impl Document {
         }
     }
 
-    pub fn processing_instruction(&self, node: Node) -> Option<ProcessingInstruction> {
+    pub fn processing_instruction(&self, node: Node) -> Option<ProcessingInstruction<'_>> {
         if matches!(self.node_type(node), NodeType::ProcessingInstruction) {
-            let s = self.node_str(node).expect("Missing PI data");
+            let s = self.node_text(node).expect("Missing PI data");
             Some(ProcessingInstruction {
                 data: BytesPI::new(s),
             })
@@ -49,39 +55,136 @@ impl Document {
     }
 
     pub fn string_value(&self, node: Node) -> String {
-        match self.node_type(node) {
-            NodeType::Document | NodeType::Element(_) => self.descendants_to_string(node),
+        let mut r = String::new();
+        self.write_string_value(node, &mut r)
+            .expect("Writing to a string cannot fail");
+        r
+    }
+
+    pub fn string_value_parts(&self, node: Node) -> impl Iterator<Item = &str> + use<'_> {
+        self.string_value_texts(node).map(borrowed)
+    }
+
+    // the parts of the string value, for any text storage
+    pub(crate) fn string_value_texts(
+        &self,
+        node: Node,
+    ) -> impl Iterator<Item = Cow<'_, str>> + use<'_> {
+        let (own, text_ids) = match self.node_type(node) {
+            // we jump to the text nodes directly by their text ids, rather
+            // than going through all descendants
+            NodeType::Document | NodeType::Element(_) => {
+                (None, self.structure.text_ids(node.get()))
+            }
             NodeType::Text | NodeType::Comment | NodeType::Attribute(_) => {
-                self.node_str(node).unwrap().to_string()
+                (Some(self.node_text(node).unwrap()), 0..0)
             }
             NodeType::ProcessingInstruction => {
-                std::str::from_utf8(self.processing_instruction(node).unwrap().content())
-                    .unwrap()
-                    .to_string()
+                let s = self.node_text(node).expect("Missing PI data");
+                let target_len = BytesPI::new(s.as_ref()).target().len();
+                let content = match s {
+                    Cow::Borrowed(s) => Cow::Borrowed(&s[target_len..]),
+                    Cow::Owned(s) => Cow::Owned(s[target_len..].to_string()),
+                };
+                (Some(content), 0..0)
             }
             NodeType::Namespace(namespace) => {
-                let uri = namespace.uri();
-                String::from_utf8(uri.to_vec()).expect("Namespace URI is not utf8")
+                let uri = std::str::from_utf8(namespace.uri()).expect("Namespace URI is not utf8");
+                (Some(Cow::Borrowed(uri)), 0..0)
             }
             NodeType::Namespaces | NodeType::Attributes => {
                 panic!("Cannot use this with namespaces or attribute node")
             }
+        };
+        own.into_iter()
+            .chain(self.text_node_texts(text_ids).map(|(_, text)| text))
+    }
+
+    pub fn write_string_value(&self, node: Node, mut w: impl fmt::Write) -> fmt::Result {
+        for part in self.string_value_texts(node) {
+            w.write_str(&part)?;
+        }
+        Ok(())
+    }
+
+    pub fn value_id(&self, node: Node) -> Option<ValueId> {
+        match self.node_type(node) {
+            NodeType::Text
+            | NodeType::Comment
+            | NodeType::ProcessingInstruction
+            | NodeType::Attribute(_) => {
+                let text_id = self.structure.text_id(node.get());
+                self.text_usage.value_id(text_id).map(|id| ValueId {
+                    document_id: self.id,
+                    id,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn text_offsets(&self) -> &TextOffsets {
+        self.text_offsets.get_or_init(|| {
+            let text_ids = self.structure.text_ids(self.root().get());
+            TextOffsets::new(self.text_node_texts(text_ids).map(|(_, text)| text.len()))
+        })
+    }
+
+    pub fn text_offset_node(&self, offset: usize) -> Option<(Node, usize)> {
+        let (i, offset) = self.text_offsets().locate(offset)?;
+        // there is a text node, so there is a node info id for it
+        let text = self.node_info_id(NodeType::Text)?;
+        let node = self.structure.select_node_info_id(i, text)?;
+        Some((Node::new(node), offset))
+    }
+
+    pub fn text_offset(&self, node: Node, offset: usize) -> Option<usize> {
+        if !matches!(
+            self.node_type(node),
+            NodeType::Document | NodeType::Element(_) | NodeType::Text
+        ) {
+            return None;
         }
+        let range = if let Some(text) = self.node_info_id(NodeType::Text) {
+            // the text nodes before the node, and before its end
+            let close = self.structure.tree().close(node.get())?;
+            let start = self.structure.rank_node_info_id(node.get(), text)?;
+            let end = self.structure.rank_node_info_id(close, text)?;
+            let text_offsets = self.text_offsets();
+            text_offsets.start(start)..text_offsets.start(end)
+        } else {
+            0..0
+        };
+        (offset <= range.len()).then_some(range.start + offset)
     }
 
     pub(crate) fn node_str(&self, node: Node) -> Option<&str> {
-        let text_id = self.structure.text_id(node.get());
-        Some(self.text_usage.text_value(text_id))
+        self.node_text(node).map(borrowed)
     }
 
-    fn descendants_to_string(&self, node: Node) -> String {
-        let texts = self.descendants(node).filter_map(|n| self.text_str(n));
-        let (lower_bound, _) = texts.size_hint();
-        let mut r = String::with_capacity(lower_bound);
-        for text in texts {
-            r.push_str(text);
+    // the text of a node, for any text storage
+    pub(crate) fn node_text(&self, node: Node) -> Option<Cow<'_, str>> {
+        match self.node_type(node) {
+            NodeType::Text
+            | NodeType::Comment
+            | NodeType::ProcessingInstruction
+            | NodeType::Attribute(_) => {
+                let text_id = self.structure.text_id(node.get());
+                Some(self.text_usage.text_value(text_id))
+            }
+            _ => None,
         }
-        r
+    }
+
+    // the texts of text nodes in a range of text ids, skipping attributes,
+    // comments and processing instructions
+    pub(crate) fn text_node_texts(
+        &self,
+        text_ids: Range<usize>,
+    ) -> impl Iterator<Item = (TextId, Cow<'_, str>)> + use<'_> {
+        self.text_usage
+            .texts(text_ids)
+            .filter(|(text_id, _)| self.is_text(Node::new(self.structure.text_index(*text_id))))
     }
 }
 
@@ -1,10 +1,64 @@
 //! Error types
 
+use std::fmt;
+
 #[derive(Debug)]
 pub(crate) enum Error {
     TooManyBitsPerElement,
 }
 
+/// An error resolving a QName such as `ex:thing` using the namespaces in
+/// scope for a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QNameError {
+    /// The string is not a valid QName.
+    Invalid(String),
+    /// The prefix of the QName is not declared.
+    UndeclaredPrefix(String),
+}
+
+impl fmt::Display for QNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QNameError::Invalid(qname) => write!(f, "invalid QName: {:?}", qname),
+            QNameError::UndeclaredPrefix(prefix) => {
+                write!(f, "undeclared namespace prefix: {:?}", prefix)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QNameError {}
+
+/// An error in an XPath expression given to [`Xoz::select`](crate::Xoz::select).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XPathError {
+    /// The expression is not valid XPath. This has the byte offset in the
+    /// expression where the problem was found and a description.
+    Syntax(usize, String),
+    /// A prefix used in the expression is not declared.
+    UndeclaredPrefix(String),
+    /// The expression is valid XPath, but uses a feature that is not
+    /// supported.
+    Unsupported(String),
+}
+
+impl fmt::Display for XPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XPathError::Syntax(position, message) => {
+                write!(f, "XPath syntax error at {}: {}", position, message)
+            }
+            XPathError::UndeclaredPrefix(prefix) => {
+                write!(f, "undeclared namespace prefix: {:?}", prefix)
+            }
+            XPathError::Unsupported(feature) => write!(f, "unsupported in XPath: {}", feature),
+        }
+    }
+}
+
+impl std::error::Error for XPathError {}
+
 /// Re-exports of QuickXML error types. These can occur during parsing.
 pub mod quickxml {
     pub use quick_xml::encoding::EncodingError;
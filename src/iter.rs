@@ -1,3 +1,8 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
 use crate::{
     document::{Document, Node},
     node_info_vec::NodeInfoId,
@@ -212,6 +217,9 @@ pub(crate) struct DescendantsIter<'a> {
     doc: &'a Document,
     root: Node,
     node: Option<Node>,
+    // the last descendant not yet yielded from the back. This is only
+    // looked up on the first next_back, as going forward doesn't need it.
+    back: Option<Option<Node>>,
 }
 
 impl<'a> DescendantsIter<'a> {
@@ -219,10 +227,19 @@ impl<'a> DescendantsIter<'a> {
         Self {
             root,
             node: doc.first_child(root),
+            back: None,
             doc,
         }
     }
 
+    fn last_descendant_or_self(doc: &Document, node: Node) -> Node {
+        let mut current = node;
+        while let Some(last_child) = doc.last_child(current) {
+            current = last_child;
+        }
+        current
+    }
+
     pub(crate) fn following(&self, node: Node) -> Option<Node> {
         // otherwise, go up parent chain until we find a next sibling
         let mut current = node;
@@ -238,6 +255,36 @@ impl<'a> DescendantsIter<'a> {
         }
         None
     }
+
+    // the node before this one in document order, within the root
+    fn preceding(&self, node: Node) -> Option<Node> {
+        if let Some(sibling) = self.doc.previous_sibling(node) {
+            Some(Self::last_descendant_or_self(self.doc, sibling))
+        } else {
+            self.doc.parent(node).filter(|parent| *parent != self.root)
+        }
+    }
+
+    fn back(&mut self) -> Option<Node> {
+        *self.back.get_or_insert_with(|| {
+            // if we're done going forward, there's nothing left either
+            self.node?;
+            self.doc
+                .last_child(self.root)
+                .map(|n| Self::last_descendant_or_self(self.doc, n))
+        })
+    }
+
+    // once the front and back meet at a node, it's the last one left
+    fn is_last(&mut self) -> bool {
+        if self.back.is_some_and(|back| back == self.node) {
+            self.node = None;
+            self.back = Some(None);
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl Iterator for DescendantsIter<'_> {
@@ -245,6 +292,9 @@ impl Iterator for DescendantsIter<'_> {
 
     fn next(&mut self) -> Option<Node> {
         let node = self.node?;
+        if self.is_last() {
+            return Some(node);
+        }
         self.node = if let Some(first_child) = self.doc.first_child(node) {
             Some(first_child)
         } else if let Some(sibling) = self.doc.next_sibling(node) {
@@ -256,6 +306,42 @@ impl Iterator for DescendantsIter<'_> {
     }
 }
 
+impl DoubleEndedIterator for DescendantsIter<'_> {
+    fn next_back(&mut self) -> Option<Node> {
+        let node = self.back()?;
+        if self.is_last() {
+            return Some(node);
+        }
+        self.back = Some(self.preceding(node));
+        Some(node)
+    }
+}
+
+pub(crate) struct LevelOrderIter<'a> {
+    doc: &'a Document,
+    queue: VecDeque<Node>,
+}
+
+impl<'a> LevelOrderIter<'a> {
+    pub(crate) fn new(doc: &'a Document, node: Node) -> Self {
+        Self {
+            doc,
+            queue: VecDeque::from([node]),
+        }
+    }
+}
+
+impl Iterator for LevelOrderIter<'_> {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        let node = self.queue.pop_front()?;
+        self.queue
+            .extend(NextSiblingIter::new(self.doc, self.doc.first_child(node)));
+        Some(node)
+    }
+}
+
 pub(crate) struct FollowingIter<'a> {
     doc: &'a Document,
     node: Option<Node>,
@@ -445,6 +531,81 @@ impl Iterator for TypedFollowingIter<'_> {
     }
 }
 
+pub(crate) struct TypedFollowingSiblingIter<'a> {
+    doc: &'a Document,
+    node: Option<Node>,
+    node_info_id: NodeInfoId,
+}
+
+impl<'a> TypedFollowingSiblingIter<'a> {
+    pub(crate) fn new(doc: &'a Document, node: Option<Node>, node_info_id: NodeInfoId) -> Self {
+        Self {
+            doc,
+            node,
+            node_info_id,
+        }
+    }
+}
+
+impl Iterator for TypedFollowingSiblingIter<'_> {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.node?;
+        self.node = self
+            .doc
+            .typed_following_sibling_by_node_info_id(node, self.node_info_id);
+        Some(node)
+    }
+}
+
+// Descendants of any of several node info ids. We keep the next descendant
+// for each node info id, and always yield the first of them, so we merge them
+// in document order.
+pub(crate) struct TypedDescendantsAnyIter<'a> {
+    doc: &'a Document,
+    // the close of the parent; descendants come before this
+    end: usize,
+    heads: BinaryHeap<Reverse<(usize, NodeInfoId)>>,
+}
+
+impl<'a> TypedDescendantsAnyIter<'a> {
+    pub(crate) fn new(doc: &'a Document, parent: Node, node_info_ids: Vec<NodeInfoId>) -> Self {
+        let end = doc
+            .structure
+            .tree()
+            .close(parent.get())
+            .expect("Tree cannot be unbalanced");
+        let mut iter = Self {
+            doc,
+            end,
+            heads: BinaryHeap::new(),
+        };
+        for node_info_id in node_info_ids {
+            iter.push_next(parent.get(), node_info_id);
+        }
+        iter
+    }
+
+    fn push_next(&mut self, i: usize, node_info_id: NodeInfoId) {
+        if let Some(next) = self.doc.structure.typed_next(i, node_info_id) {
+            if next < self.end {
+                self.heads.push(Reverse((next, node_info_id)));
+            }
+        }
+    }
+}
+
+impl Iterator for TypedDescendantsAnyIter<'_> {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((i, node_info_id)) = self.heads.pop()?;
+        self.push_next(i, node_info_id);
+        Some(Node::new(i))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,6 +58,8 @@
 
 mod access;
 mod builder;
+mod child_indexes;
+mod compressed_text;
 mod document;
 pub mod error;
 mod iter;
@@ -66,18 +68,27 @@ mod mta_compiler;
 mod name;
 mod node;
 mod node_info_vec;
+mod node_set;
 mod parser;
 mod serializer;
 mod structure;
 mod text;
 mod text_fm;
+mod text_offsets;
 mod textsearch;
 mod traverse;
 mod tree_builder;
+mod visitor;
+mod word_index;
 mod xozdata;
+mod xpath;
 
 pub use document::ProcessingInstruction;
 pub use name::{Namespace, NodeName};
 pub use node::NodeType;
+pub use node_set::NodeSet;
+pub use text::{TextStorage, ValueId};
 pub use traverse::TraverseState;
-pub use xozdata::{Node, Xoz};
+pub use visitor::{VisitControl, Visitor};
+pub use word_index::WordQuery;
+pub use xozdata::{Node, NodeId, NodeRef, Snippet, Xoz};
@@ -1,12 +1,14 @@
 // this is a WIP
 #![allow(dead_code)]
 
-use std::hash::Hash;
+use std::{cell::OnceCell, hash::Hash};
 
 use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
 
 use crate::{
     document::{Document, Node},
+    node_info_vec::NodeInfoId,
+    node_set::SortedSet,
     NodeType,
 };
 
@@ -30,14 +32,32 @@ impl State {
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct FormulaId(usize);
 
-pub(crate) type Nodes = HashSet<Node>;
+// kept in document order, so results need no sorting afterward
+pub(crate) type Nodes = SortedSet<Node>;
 pub(crate) type Mapping = HashMap<State, Nodes>;
 
+// Jumping with many tags takes a rank and select for each of them at every
+// node we jump to, which costs more than going through the nodes one by one.
+const MAX_JUMP_TAGS: usize = 8;
+
 pub(crate) struct Automaton {
     formulas: Vec<Formula>,
     state_lookup: StateLookupFormula,
     start_state: State,
     bottom_states: States,
+    // the states a run can jump in, found at the start of the first run
+    jumps: OnceCell<HashMap<State, Jump>>,
+}
+
+// A state that goes down and right at every node, so that it scans the
+// descendants of a node and of its following siblings, and only does
+// something else at nodes with particular tags. A run in such a state can
+// jump from one node with those tags to the next, rather than going through
+// the whole forest.
+struct Jump {
+    tags: Vec<NodeInfoId>,
+    // the transitions that go down and right in the state itself
+    loops: [FormulaId; 2],
 }
 
 impl Automaton {
@@ -47,6 +67,7 @@ impl Automaton {
             state_lookup: StateLookupFormula::new(),
             start_state: State::new(),
             bottom_states: States::new(),
+            jumps: OnceCell::new(),
         }
     }
 
@@ -70,58 +91,380 @@ impl Automaton {
     }
 
     pub(crate) fn run(&self, document: &Document, node: Node) -> Nodes {
-        let mut states = States::new();
-        states.insert(self.start_state);
-        let mut mapping = self.top_down_run(document, Some(node), states);
-        mapping.remove(&self.start_state).unwrap_or_default()
+        self.run_from(document, &Nodes::single(node))
+    }
+
+    // The nodes selected from any of the nodes. Rather than run from each
+    // node on its own, we go down from the root once and start the start
+    // state at each of the nodes on the way, so that a subtree several of
+    // them share is only gone through once for the states they have in
+    // common.
+    pub(crate) fn run_from(&self, document: &Document, nodes: &Nodes) -> Nodes {
+        // the attributes or namespaces after an attribute or namespace node
+        // are not its siblings, so we never go right from one we start at.
+        // We run from those on their own.
+        let (held, nodes): (Vec<Node>, Vec<Node>) = nodes
+            .iter()
+            .partition(|node| document.is_attribute(*node) || document.is_namespace(*node));
+        let mut seek = Seek {
+            start: self.start_state,
+            nodes: &nodes,
+            selected: Vec::new(),
+        };
+        for node in held {
+            let mut states = States::new();
+            states.insert(self.start_state);
+            let mut mapping = self.run_forest(document, node, None, states, None);
+            seek.selected
+                .extend(mapping.remove(&self.start_state).unwrap_or_default());
+        }
+        if !nodes.is_empty() {
+            self.run_forest(
+                document,
+                document.root(),
+                None,
+                States::new(),
+                Some(&mut seek),
+            );
+        }
+        seek.selected.into_iter().collect()
     }
 
-    pub(crate) fn top_down_run(
+    // The run goes over the primitive tree, so that the nodes holding the
+    // attributes and namespaces of an element are its first children.
+    fn top_down_run(
         &self,
         document: &Document,
         node: Option<Node>,
         states: States,
+        seek: Option<&mut Seek>,
+    ) -> Mapping {
+        match node {
+            Some(node) => {
+                self.run_forest(document, node, document.next_sibling(node), states, seek)
+            }
+            None => self.bottom_mapping(&states),
+        }
+    }
+
+    // The run over the forest of a node and its following siblings, where
+    // next is the first of those. A run goes right once for every sibling,
+    // so rather than recurse we go along the siblings in a loop, and then
+    // evaluate them back to front, each with the mapping of the siblings
+    // after it. If we seek nodes to start at, we go to the ones in the
+    // forest even where there are no states.
+    fn run_forest(
+        &self,
+        document: &Document,
+        node: Node,
+        next: Option<Node>,
+        states: States,
+        mut seek: Option<&mut Seek>,
     ) -> Mapping {
-        if let Some(node) = node {
+        let jumps = self.jumps(document);
+        // the forest ends where the parent of the node does
+        let end = seek
+            .is_some()
+            .then(|| document.primitive_close(document.primitive_parent(node).unwrap_or(node)));
+        let mut steps = Vec::new();
+        let mut current = Some(node);
+        let mut next = next;
+        let mut states = states;
+        let mut mapping = loop {
+            let Some(mut node) = current else {
+                break self.bottom_mapping(&states);
+            };
+            // the next node to start at in the rest of the forest
+            let target = seek
+                .as_deref()
+                .zip(end)
+                .and_then(|(seek, end)| seek.next(node, end));
+            if states.is_empty() {
+                let Some(target) = target else {
+                    // nothing can happen in the rest of the forest
+                    break Mapping::new();
+                };
+                // nothing happens until the sibling that holds the target
+                node = document.primitive_sibling_ancestor(target, node);
+                next = document.next_sibling(node);
+            }
+            let starts = seek.as_deref().is_some_and(|seek| seek.starts_at(node));
+            // the start state can also come from the left
+            let inherited = states.contains(&self.start_state);
+            if starts {
+                states.insert(self.start_state);
+            }
+            // each state is run on its own, so we can jump in some of them
+            // and go through the nodes one by one in others
+            let (jumping, stepping): (States, States) = states
+                .into_iter()
+                .partition(|state| jumps.contains_key(state));
+            let jumped = if jumping.is_empty() {
+                Mapping::new()
+            } else {
+                self.jumping_run(document, node, jumping)
+            };
+            if stepping.is_empty() && target.is_none() {
+                break jumped;
+            }
             let trans = self
                 .state_lookup
-                .matching(&states, document.node_type(node));
-            let mut left_states = States::new();
-            let mut right_states = States::new();
-            for (_q, formula_id) in &trans {
-                let formula = &self.formulas[formula_id.0];
-                left_states.extend(formula.down_left());
-                right_states.extend(formula.down_right());
+                .matching(&stepping, document.node_info_id_for_node(node));
+            let (left_states, right_states) = self.down_states(&trans);
+            let left_mapping = self.top_down_run(
+                document,
+                document.primitive_first_child(node),
+                left_states,
+                seek.as_deref_mut(),
+            );
+            steps.push((
+                node,
+                trans,
+                left_mapping,
+                jumped,
+                starts.then_some(inherited),
+            ));
+            states = right_states;
+            current = next;
+            next = current.and_then(|node| document.next_sibling(node));
+        };
+        for (node, trans, left_mapping, jumped, start) in steps.into_iter().rev() {
+            let mut node_mapping = self.evaluate(document, node, trans, left_mapping, mapping);
+            node_mapping.extend(jumped);
+            if let (Some(inherited), Some(seek)) = (start, seek.as_deref_mut()) {
+                // what we select from here, leaving it for the left if it
+                // needs it too
+                let nodes = if inherited {
+                    node_mapping.get(&self.start_state).cloned()
+                } else {
+                    node_mapping.remove(&self.start_state)
+                };
+                seek.selected.extend(nodes.unwrap_or_default());
             }
-            let left_mapping = self.top_down_run(document, document.first_child(node), left_states);
-            let right_mapping =
-                self.top_down_run(document, document.next_sibling(node), right_states);
-            let mut mapping = Mapping::new();
-            for (q, formula_id) in trans {
-                let formula = &self.formulas[formula_id.0];
-                let outcome = formula.evaluate(node, &left_mapping, &right_mapping);
-                if outcome.b {
-                    mapping.entry(q).or_default().extend(outcome.r);
-                }
+            mapping = node_mapping;
+        }
+        mapping
+    }
+
+    fn bottom_mapping(&self, states: &States) -> Mapping {
+        let mut mapping = Mapping::new();
+        for state in states {
+            if self.bottom_states.contains(state) {
+                mapping.insert(*state, Nodes::new());
             }
-            mapping
+        }
+        mapping
+    }
+
+    // The run of jump states over the forest of the node and its following
+    // siblings. As these states only pass themselves on at all other nodes,
+    // what they give is what they give at the nodes with their tags in the
+    // forest, which we find in document order by rank and select.
+    fn jumping_run(&self, document: &Document, node: Node, states: States) -> Mapping {
+        let jumps = self.jumps(document);
+        let mut tags = states
+            .iter()
+            .flat_map(|state| jumps[state].tags.iter().copied())
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        // the forest ends where the parent of the node does
+        let end = document.primitive_close(document.primitive_parent(node).unwrap_or(node));
+        let mut mapping = self.bottom_mapping(&states);
+        let mut current = if tags.contains(&document.node_info_id_for_node(node)) {
+            Some(node)
         } else {
-            let mut mapping = Mapping::new();
-            for state in states {
-                if self.bottom_states.contains(&state) {
-                    mapping.insert(state, Nodes::new());
+            next_with_tags(document, node, &tags)
+        };
+        while let Some(node) = current.filter(|node| node.get() < end) {
+            // the loops are already taken care of by jumping
+            let trans = self
+                .state_lookup
+                .matching(&states, document.node_info_id_for_node(node))
+                .into_iter()
+                .filter(|(state, formula_id)| !jumps[state].loops.contains(formula_id))
+                .collect::<Vec<_>>();
+            let node_mapping =
+                self.evaluate_node(document, node, document.next_sibling(node), trans);
+            // the nodes come in document order, so this appends them
+            for (state, nodes) in node_mapping {
+                mapping.entry(state).or_default().append(nodes);
+            }
+            current = next_with_tags(document, node, &tags);
+        }
+        mapping
+    }
+
+    fn jumps(&self, document: &Document) -> &HashMap<State, Jump> {
+        self.jumps.get_or_init(|| {
+            self.state_lookup
+                .states
+                .keys()
+                .filter_map(|state| Some((*state, self.jump(document, *state)?)))
+                .collect()
+        })
+    }
+
+    // The tags of a jump state can't be those of nodes that hold attributes
+    // and namespaces, or that are held by them, as a state that scans
+    // descendants doesn't go into a holder.
+    fn jump(&self, document: &Document, state: State) -> Option<Jump> {
+        let is_held = |tag: &NodeInfoId| {
+            matches!(
+                document.node_type_for_node_info_id(*tag),
+                NodeType::Attribute(_) | NodeType::Namespace(_)
+            )
+        };
+        let is_holder = |tag: &NodeInfoId| {
+            matches!(
+                document.node_type_for_node_info_id(*tag),
+                NodeType::Attributes | NodeType::Namespaces
+            )
+        };
+        let tag_lookup = self.state_lookup.states.get(&state)?;
+        let mut down_left = None;
+        let mut down_right = None;
+        for (tags, formula_id) in &tag_lookup.excludes {
+            match &self.formulas[formula_id.0] {
+                Formula::DownLeft(s) if *s == state && tags.iter().all(is_holder) => {
+                    down_left = Some(*formula_id)
                 }
+                Formula::DownRight(s) if *s == state && tags.is_empty() => {
+                    down_right = Some(*formula_id)
+                }
+                _ => return None,
             }
-            mapping
         }
+        let tags = tag_lookup.includes.keys().copied().collect::<Vec<_>>();
+        if tags.len() > MAX_JUMP_TAGS || tags.iter().any(|tag| is_held(tag) || is_holder(tag)) {
+            return None;
+        }
+        Some(Jump {
+            tags,
+            loops: [down_left?, down_right?],
+        })
+    }
+
+    // the states the transitions go into on the left and on the right
+    fn down_states(&self, trans: &[(State, FormulaId)]) -> (States, States) {
+        let mut left_states = States::new();
+        let mut right_states = States::new();
+        for (_q, formula_id) in trans {
+            let formula = &self.formulas[formula_id.0];
+            left_states.extend(formula.down_left());
+            right_states.extend(formula.down_right());
+        }
+        (left_states, right_states)
+    }
+
+    // run the subtrees the transitions go into, and evaluate them
+    fn evaluate_node(
+        &self,
+        document: &Document,
+        node: Node,
+        next: Option<Node>,
+        trans: Vec<(State, FormulaId)>,
+    ) -> Mapping {
+        let (left_states, right_states) = self.down_states(&trans);
+        let left_mapping = self.top_down_run(
+            document,
+            document.primitive_first_child(node),
+            left_states,
+            None,
+        );
+        let right_mapping = self.top_down_run(document, next, right_states, None);
+        self.evaluate(document, node, trans, left_mapping, right_mapping)
+    }
+
+    // evaluate the transitions at a node, given the mappings of its subtree
+    // and of its following siblings
+    fn evaluate(
+        &self,
+        document: &Document,
+        node: Node,
+        trans: Vec<(State, FormulaId)>,
+        mut left_mapping: Mapping,
+        mut right_mapping: Mapping,
+    ) -> Mapping {
+        let outcomes = trans
+            .into_iter()
+            .filter_map(|(q, formula_id)| {
+                let formula = &self.formulas[formula_id.0];
+                let outcome = formula.evaluate(document, node, &left_mapping, &right_mapping);
+                outcome.b.then_some((q, outcome.r))
+            })
+            .collect::<Vec<_>>();
+        // the nodes of a subtree go to the last state that uses them, and
+        // are only copied for the ones before it
+        let mut uses = HashMap::<Source, usize>::new();
+        for source in outcomes.iter().flat_map(|(_, sources)| sources) {
+            *uses.entry(*source).or_default() += 1;
+        }
+        let mut mapping = Mapping::new();
+        for (q, sources) in outcomes {
+            let nodes = mapping.entry(q).or_default();
+            for source in sources {
+                let remaining = uses.get_mut(&source).unwrap();
+                *remaining -= 1;
+                let last = *remaining == 0;
+                nodes.append(match source {
+                    Source::Mark => Nodes::single(node),
+                    Source::Left(state) => take(&mut left_mapping, state, last),
+                    Source::Right(state) => take(&mut right_mapping, state, last),
+                });
+            }
+        }
+        mapping
     }
 
     pub(crate) fn start_state(&self) -> State {
         self.start_state
     }
+
+    #[cfg(test)]
+    pub(crate) fn jump_states(&self, document: &Document) -> usize {
+        self.jumps(document).len()
+    }
 }
 
-#[derive(Debug)]
+// The nodes a run starts the start state at, other than attributes and
+// namespaces, in document order, and the nodes selected from them.
+struct Seek<'a> {
+    start: State,
+    nodes: &'a [Node],
+    selected: Vec<Node>,
+}
+
+impl Seek<'_> {
+    // the first node to start at from the node on, if it's before end
+    fn next(&self, node: Node, end: usize) -> Option<Node> {
+        let i = self.nodes.partition_point(|n| *n < node);
+        self.nodes.get(i).copied().filter(|n| n.get() < end)
+    }
+
+    fn starts_at(&self, node: Node) -> bool {
+        self.nodes.binary_search(&node).is_ok()
+    }
+}
+
+// the nodes of a state in a mapping, taking them out if nothing else needs
+// them
+fn take(mapping: &mut Mapping, state: State, last: bool) -> Nodes {
+    if last {
+        mapping.remove(&state).unwrap_or_default()
+    } else {
+        mapping.get(&state).cloned().unwrap_or_default()
+    }
+}
+
+// the first node after the node in document order with any of the tags
+fn next_with_tags(document: &Document, node: Node, tags: &[NodeInfoId]) -> Option<Node> {
+    tags.iter()
+        .filter_map(|tag| document.typed_next_by_node_info_id(node, *tag))
+        .min()
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum Formula {
     True,
     False,
@@ -162,75 +505,38 @@ impl Formula {
         })
     }
 
-    fn evaluate(&self, node: Node, left: &Mapping, right: &Mapping) -> FormulaOutcome {
+    fn evaluate(
+        &self,
+        document: &Document,
+        node: Node,
+        left: &Mapping,
+        right: &Mapping,
+    ) -> FormulaOutcome {
         match self {
-            Formula::True => FormulaOutcome {
-                b: true,
-                r: Nodes::new(),
-            },
+            Formula::True => FormulaOutcome::new(true),
             Formula::Mark => FormulaOutcome {
                 b: true,
-                r: {
-                    let mut nodes = Nodes::new();
-                    nodes.insert(node);
-                    nodes
-                },
+                r: vec![Source::Mark],
             },
             Formula::And(and) => {
-                let left_outcome = and.left.evaluate(node, left, right);
-                let right_outcome = and.right.evaluate(node, left, right);
-                left_outcome.and(&right_outcome)
+                let left_outcome = and.left.evaluate(document, node, left, right);
+                let right_outcome = and.right.evaluate(document, node, left, right);
+                left_outcome.and(right_outcome)
             }
             Formula::Or(or) => {
-                let left_outcome = or.left.evaluate(node, left, right);
-                let right_outcome = or.right.evaluate(node, left, right);
-                left_outcome.or(&right_outcome)
+                let left_outcome = or.left.evaluate(document, node, left, right);
+                let right_outcome = or.right.evaluate(document, node, left, right);
+                left_outcome.or(right_outcome)
             }
             Formula::Not(not) => {
-                let inner = not.inner.evaluate(node, left, right);
+                let inner = not.inner.evaluate(document, node, left, right);
                 inner.not()
             }
-            Formula::DownLeft(state) => {
-                let nodes = left.get(state).cloned().unwrap_or_default();
-                FormulaOutcome { b: true, r: nodes }
-                // if let Some(nodes) = nodes {
-                //     if nodes.contains(&node) {
-                //         return FormulaOutcome {
-                //             b: true,
-                //             r: nodes.clone(),
-                //         };
-                //     }
-                // }
-                // FormulaOutcome {
-                //     b: false,
-                //     r: Nodes::new(),
-                // }
-            }
-            Formula::DownRight(state) => {
-                let nodes = right.get(state).cloned().unwrap_or_default();
-                FormulaOutcome { b: true, r: nodes }
-
-                // let nodes = right.get(state);
-                // if let Some(nodes) = nodes {
-                //     if nodes.contains(&node) {
-                //         return FormulaOutcome {
-                //             b: true,
-                //             r: nodes.clone(),
-                //         };
-                //     }
-                // }
-                // FormulaOutcome {
-                //     b: false,
-                //     r: Nodes::new(),
-                // }
-            }
-            Formula::Pred(_pred) => {
-                todo!()
-            }
-            Formula::False => FormulaOutcome {
-                b: false,
-                r: Nodes::new(),
-            },
+            // a state is accepted in a subtree if it has a mapping there
+            Formula::DownLeft(state) => FormulaOutcome::down(left, *state, Source::Left(*state)),
+            Formula::DownRight(state) => FormulaOutcome::down(right, *state, Source::Right(*state)),
+            Formula::Pred(pred) => FormulaOutcome::new(pred.evaluate(document, node)),
+            Formula::False => FormulaOutcome::new(false),
         }
     }
 
@@ -284,78 +590,116 @@ impl Formula {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct And {
     pub(crate) left: Box<Formula>,
     pub(crate) right: Box<Formula>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Or {
     pub(crate) left: Box<Formula>,
     pub(crate) right: Box<Formula>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Not {
     pub(crate) inner: Box<Formula>,
 }
 
-#[derive(Debug)]
-pub(crate) struct Predicate;
+// a test of the node itself, beyond its tag
+#[derive(Debug, Clone)]
+pub(crate) enum Pred {
+    StringValueEquals(String),
+    StringValueNotEquals(String),
+    // a processing instruction with this target
+    ProcessingInstructionTarget(String),
+}
 
-#[derive(Debug)]
-pub(crate) struct Pred {
-    pred: Predicate,
+impl Pred {
+    fn evaluate(&self, document: &Document, node: Node) -> bool {
+        match self {
+            Pred::StringValueEquals(value) => string_value_equals(document, node, value),
+            Pred::StringValueNotEquals(value) => !string_value_equals(document, node, value),
+            Pred::ProcessingInstructionTarget(target) => document
+                .processing_instruction(node)
+                .is_some_and(|pi| pi.target() == target.as_bytes()),
+        }
+    }
+}
+
+// compare part by part, so we don't have to build the string value
+fn string_value_equals(document: &Document, node: Node, value: &str) -> bool {
+    let mut rest = value;
+    for part in document.string_value_texts(node) {
+        match rest.strip_prefix(part.as_ref()) {
+            Some(remaining) => rest = remaining,
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+// Where the nodes a formula selects come from. We only get the nodes once we
+// know which formulas hold at a node, so that we can move the nodes of a
+// subtree into the result rather than copy them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Source {
+    Mark,
+    Left(State),
+    Right(State),
 }
 
 #[derive(Debug)]
 pub(crate) struct FormulaOutcome {
     b: bool,
-    r: Nodes,
+    r: Vec<Source>,
 }
 
 impl FormulaOutcome {
-    fn not(&self) -> FormulaOutcome {
-        FormulaOutcome {
-            b: !self.b,
-            r: Nodes::new(),
-        }
+    fn new(b: bool) -> FormulaOutcome {
+        FormulaOutcome { b, r: Vec::new() }
     }
 
-    fn and(&self, other: &FormulaOutcome) -> FormulaOutcome {
-        if self.b && other.b {
+    fn down(mapping: &Mapping, state: State, source: Source) -> FormulaOutcome {
+        if mapping.contains_key(&state) {
             FormulaOutcome {
                 b: true,
-                r: self.r.union(&other.r).cloned().collect(),
+                r: vec![source],
             }
         } else {
-            FormulaOutcome {
-                b: false,
-                r: Nodes::new(),
-            }
+            FormulaOutcome::new(false)
         }
     }
 
-    fn or(&self, other: &FormulaOutcome) -> FormulaOutcome {
+    fn not(&self) -> FormulaOutcome {
+        FormulaOutcome::new(!self.b)
+    }
+
+    fn and(self, other: FormulaOutcome) -> FormulaOutcome {
+        if self.b && other.b {
+            self.join(other)
+        } else {
+            FormulaOutcome::new(false)
+        }
+    }
+
+    fn or(self, other: FormulaOutcome) -> FormulaOutcome {
         match (self.b, other.b) {
-            (true, true) => FormulaOutcome {
-                b: true,
-                r: self.r.union(&other.r).cloned().collect(),
-            },
-            (true, false) => FormulaOutcome {
-                b: true,
-                r: self.r.clone(),
-            },
-            (false, true) => FormulaOutcome {
-                b: true,
-                r: other.r.clone(),
-            },
-            (false, false) => FormulaOutcome {
-                b: false,
-                r: Nodes::new(),
-            },
+            (true, true) => self.join(other),
+            (true, false) => self,
+            (false, true) => other,
+            (false, false) => FormulaOutcome::new(false),
+        }
+    }
+
+    fn join(mut self, other: FormulaOutcome) -> FormulaOutcome {
+        for source in other.r {
+            if !self.r.contains(&source) {
+                self.r.push(source);
+            }
         }
+        self
     }
 }
 
@@ -380,7 +724,7 @@ impl<T: Copy> StateLookup<T> {
         self.states.get_mut(&state)
     }
 
-    fn matching(&self, states: &States, tag: &NodeType) -> Vec<(State, T)> {
+    fn matching(&self, states: &States, tag: NodeInfoId) -> Vec<(State, T)> {
         let mut results = Vec::new();
 
         for state in states {
@@ -399,11 +743,13 @@ impl<T: Copy> StateLookup<T> {
 
 pub(crate) type TagLookupFormula = TagLookup<FormulaId>;
 
+// Tags are node info ids, so that we can look up the node info id of a node
+// in the document directly, without comparing node types.
 pub(crate) struct TagLookup<T: Copy> {
     // Direct mapping for includes
-    includes: HashMap<NodeType<'static>, Vec<T>>,
+    includes: HashMap<NodeInfoId, Vec<T>>,
     // For excludes, we store (excluded_tags, payload) pairs
-    excludes: Vec<(HashSet<NodeType<'static>>, T)>,
+    excludes: Vec<(HashSet<NodeInfoId>, T)>,
 }
 
 impl<T: Copy> TagLookup<T> {
@@ -429,11 +775,11 @@ impl<T: Copy> TagLookup<T> {
         }
     }
 
-    fn matching(&self, tag: &NodeType) -> Vec<T> {
+    fn matching(&self, tag: NodeInfoId) -> Vec<T> {
         let mut results = Vec::new();
 
         // Add all direct matches from includes
-        if let Some(payloads) = self.includes.get(tag) {
+        if let Some(payloads) = self.includes.get(&tag) {
             results.extend(payloads.iter().cloned());
         }
 
@@ -441,7 +787,7 @@ impl<T: Copy> TagLookup<T> {
         results.extend(
             self.excludes
                 .iter()
-                .filter(|(tags, _)| !tags.contains(tag))
+                .filter(|(tags, _)| !tags.contains(&tag))
                 .map(|(_, payload)| payload),
         );
 
@@ -451,36 +797,69 @@ impl<T: Copy> TagLookup<T> {
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub(crate) enum Guard {
-    Includes(HashSet<NodeType<'static>>),
-    Excludes(HashSet<NodeType<'static>>),
+    Includes(HashSet<NodeInfoId>),
+    Excludes(HashSet<NodeInfoId>),
 }
 
 impl Guard {
-    pub(crate) fn includes(tags: Vec<NodeType>) -> Self {
-        Guard::Includes(tags.into_iter().map(|t| t.into_owned()).collect())
+    pub(crate) fn includes(tags: Vec<NodeInfoId>) -> Self {
+        Guard::Includes(tags.into_iter().collect())
     }
-    pub(crate) fn excludes(tags: Vec<NodeType>) -> Self {
-        Guard::Excludes(tags.into_iter().map(|t| t.into_owned()).collect())
+    pub(crate) fn excludes(tags: Vec<NodeInfoId>) -> Self {
+        Guard::Excludes(tags.into_iter().collect())
     }
 
-    pub(crate) fn include(tag: NodeType) -> Self {
-        Guard::Includes([tag].into_iter().map(|t| t.into_owned()).collect())
+    pub(crate) fn include(tag: NodeInfoId) -> Self {
+        Guard::Includes([tag].into_iter().collect())
     }
 
-    pub(crate) fn exclude(tag: NodeType) -> Self {
-        Guard::Excludes([tag].into_iter().map(|t| t.into_owned()).collect())
+    pub(crate) fn exclude(tag: NodeInfoId) -> Self {
+        Guard::Excludes([tag].into_iter().collect())
     }
 
     pub(crate) fn all() -> Self {
         // excluding nothing is including anything
         Guard::Excludes(HashSet::new())
     }
+
+    // the guard that matches the tags both guards match
+    pub(crate) fn intersection(&self, other: &Guard) -> Guard {
+        match (self, other) {
+            (Guard::Includes(a), Guard::Includes(b)) => {
+                Guard::Includes(a.intersection(b).cloned().collect())
+            }
+            (Guard::Includes(a), Guard::Excludes(b)) | (Guard::Excludes(b), Guard::Includes(a)) => {
+                Guard::Includes(a.difference(b).cloned().collect())
+            }
+            (Guard::Excludes(a), Guard::Excludes(b)) => {
+                Guard::Excludes(a.union(b).cloned().collect())
+            }
+        }
+    }
+
+    // the guard that matches the tags this guard doesn't
+    pub(crate) fn complement(&self) -> Guard {
+        match self {
+            Guard::Includes(tags) => Guard::Excludes(tags.clone()),
+            Guard::Excludes(tags) => Guard::Includes(tags.clone()),
+        }
+    }
+
+    pub(crate) fn is_nothing(&self) -> bool {
+        matches!(self, Guard::Includes(tags) if tags.is_empty())
+    }
+
+    pub(crate) fn matches(&self, tag: NodeInfoId) -> bool {
+        match self {
+            Guard::Includes(tags) => tags.contains(&tag),
+            Guard::Excludes(tags) => !tags.contains(&tag),
+        }
+    }
 }
+
 #[cfg(test)]
 mod tests {
 
-    use crate::name::NodeName;
-
     use super::*;
 
     #[test]
@@ -488,56 +867,72 @@ mod tests {
         let mut lookup = TagLookup::new();
 
         // Test includes
-        let guard = Guard::Includes(
-            [NodeType::Element(NodeName::new("", "foo"))]
-                .into_iter()
-                .collect(),
-        );
+        let guard = Guard::Includes([NodeInfoId::new(20)].into_iter().collect());
         lookup.add(guard.clone(), "value1");
 
         // Add another payload for the same tag
         lookup.add(guard, "value2");
 
-        let foo_tag = NodeType::Element(NodeName::new("", "foo"));
+        let foo_tag = NodeInfoId::new(20);
 
-        let bar_tag = NodeType::Element(NodeName::new("", "bar"));
+        let bar_tag = NodeInfoId::new(22);
 
-        assert_eq!(lookup.matching(&foo_tag), vec!["value1", "value2"]);
+        assert_eq!(lookup.matching(foo_tag), vec!["value1", "value2"]);
 
-        assert_eq!(lookup.matching(&bar_tag), Vec::<&str>::new());
+        assert_eq!(lookup.matching(bar_tag), Vec::<&str>::new());
     }
 
     #[test]
     fn test_tag_lookup_excludes() {
         let mut lookup = TagLookup::new();
 
-        let foo_tag = NodeType::Element(NodeName::new("", "foo"));
+        let foo_tag = NodeInfoId::new(20);
 
-        let bar_tag = NodeType::Element(NodeName::new("", "bar"));
+        let bar_tag = NodeInfoId::new(22);
 
         // Test excludes
-        let exclude_guard =
-            Guard::Excludes([foo_tag.clone(), bar_tag.clone()].into_iter().collect());
+        let exclude_guard = Guard::Excludes([foo_tag, bar_tag].into_iter().collect());
         lookup.add(exclude_guard, "excluded");
 
         // Excluded tags should not match
-        assert_eq!(lookup.matching(&foo_tag), Vec::<&str>::new());
-        assert_eq!(lookup.matching(&bar_tag), Vec::<&str>::new());
+        assert_eq!(lookup.matching(foo_tag), Vec::<&str>::new());
+        assert_eq!(lookup.matching(bar_tag), Vec::<&str>::new());
 
         // Non-excluded tag should match
-        let baz_tag = NodeType::Element(NodeName::new("", "baz"));
-        assert_eq!(lookup.matching(&baz_tag), vec!["excluded"]);
+        let baz_tag = NodeInfoId::new(24);
+        assert_eq!(lookup.matching(baz_tag), vec!["excluded"]);
 
         // Test combination of includes and excludes
-        let include_guard = Guard::Includes([foo_tag.clone()].into_iter().collect());
+        let include_guard = Guard::Includes([foo_tag].into_iter().collect());
         lookup.add(include_guard, "included");
 
         // foo is excluded but also included
-        assert_eq!(lookup.matching(&foo_tag), vec!["included"]);
+        assert_eq!(lookup.matching(foo_tag), vec!["included"]);
         // bar is just excluded
-        assert_eq!(lookup.matching(&bar_tag), Vec::<&str>::new());
+        assert_eq!(lookup.matching(bar_tag), Vec::<&str>::new());
         // baz matches the exclude guard
-        assert_eq!(lookup.matching(&baz_tag), vec!["excluded"]);
+        assert_eq!(lookup.matching(baz_tag), vec!["excluded"]);
+    }
+
+    #[test]
+    fn test_guard_intersection() {
+        let foo_tag = NodeInfoId::new(20);
+        let bar_tag = NodeInfoId::new(22);
+        let foo_or_bar = Guard::includes(vec![foo_tag, bar_tag]);
+        let not_foo = Guard::exclude(foo_tag);
+
+        assert_eq!(foo_or_bar.intersection(&not_foo), Guard::include(bar_tag));
+        assert_eq!(
+            not_foo.intersection(&Guard::exclude(bar_tag)),
+            Guard::excludes(vec![foo_tag, bar_tag])
+        );
+        assert_eq!(
+            foo_or_bar.intersection(&Guard::include(NodeInfoId::new(2))),
+            Guard::includes(vec![])
+        );
+        assert_eq!(Guard::all().intersection(&not_foo), not_foo);
+        assert!(not_foo.matches(bar_tag));
+        assert!(!not_foo.matches(foo_tag));
     }
 
     #[test]
@@ -547,33 +942,27 @@ mod tests {
         let state2 = State(2);
         let mut tag_lookup1 = TagLookup::new();
         let mut tag_lookup2 = TagLookup::new();
-        let foo_tag = NodeType::Element(NodeName::new("", "foo"));
-        let bar_tag = NodeType::Element(NodeName::new("", "bar"));
-        tag_lookup1.add(
-            Guard::Includes([foo_tag.clone()].into_iter().collect()),
-            "value1",
-        );
-        tag_lookup2.add(
-            Guard::Includes([bar_tag.clone()].into_iter().collect()),
-            "value2",
-        );
+        let foo_tag = NodeInfoId::new(20);
+        let bar_tag = NodeInfoId::new(22);
+        tag_lookup1.add(Guard::Includes([foo_tag].into_iter().collect()), "value1");
+        tag_lookup2.add(Guard::Includes([bar_tag].into_iter().collect()), "value2");
         lookup.add(state1, tag_lookup1);
         lookup.add(state2, tag_lookup2);
 
         let states = [state1, state2].iter().cloned().collect();
-        assert_eq!(lookup.matching(&states, &foo_tag), vec![(state1, "value1")]);
-        assert_eq!(lookup.matching(&states, &bar_tag), vec![(state2, "value2")]);
+        assert_eq!(lookup.matching(&states, foo_tag), vec![(state1, "value1")]);
+        assert_eq!(lookup.matching(&states, bar_tag), vec![(state2, "value2")]);
         let states = [state1].iter().cloned().collect();
-        assert_eq!(lookup.matching(&states, &foo_tag), vec![(state1, "value1")]);
+        assert_eq!(lookup.matching(&states, foo_tag), vec![(state1, "value1")]);
         assert_eq!(
-            lookup.matching(&states, &bar_tag),
+            lookup.matching(&states, bar_tag),
             Vec::<(State, &str)>::new()
         );
         let states = [state2].iter().cloned().collect();
         assert_eq!(
-            lookup.matching(&states, &foo_tag),
+            lookup.matching(&states, foo_tag),
             Vec::<(State, &str)>::new()
         );
-        assert_eq!(lookup.matching(&states, &bar_tag), vec![(state2, "value2")]);
+        assert_eq!(lookup.matching(&states, bar_tag), vec![(state2, "value2")]);
     }
 }
@@ -1,21 +1,39 @@
 // module level allow dead code for now, as this is a WIP
 #![allow(dead_code)]
 
+use ahash::{HashSet, HashSetExt};
+
 use crate::{
-    mta::{Automaton, Formula, Guard, State},
-    name::NodeName,
+    document::{Document, Node},
+    error::XPathError,
+    mta::{self, Automaton, Formula, Guard, Nodes, State},
+    node_info_vec::{
+        NodeInfoId, ATTRIBUTES_NODE_INFO_OPEN_ID, COMMENT_NODE_INFO_OPEN_ID,
+        NAMESPACES_NODE_INFO_OPEN_ID, PROCESSING_INSTRUCTION_NODE_INFO_OPEN_ID,
+        TEXT_NODE_INFO_OPEN_ID,
+    },
     NodeType,
 };
 
-enum Axis {
-    Descendant,
-    Child,
-    Self_,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    Ancestor,
+    AncestorOrSelf,
     Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
     FollowingSibling,
+    Namespace,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    Self_,
 }
 
-enum NodeTest {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NodeTest {
     TagName {
         // none is match everything
         // empty url is match default namespace
@@ -25,122 +43,602 @@ enum NodeTest {
     },
     Text,
     Node,
+    Comment,
+    // with the target, if given
+    ProcessingInstruction(Option<String>),
 }
 
-struct LocationStep {
-    axis: Axis,
-    node_test: NodeTest,
-    predicate: Option<Pred>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocationStep {
+    pub(crate) axis: Axis,
+    pub(crate) node_test: NodeTest,
+    pub(crate) predicate: Option<Pred>,
 }
 
-enum Pred {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Pred {
     And(Box<Pred>, Box<Pred>),
     Or(Box<Pred>, Box<Pred>),
     Not(Box<Pred>),
     Core(Core),
-    // need extension for predicate functions and equality for text nodes
+    // the string value of a node selected by the path is the string
+    Equals(Core, String),
+    // the string value of a node selected by the path is not the string
+    NotEquals(Core, String),
 }
 
-struct LocationPath {
-    steps: Vec<LocationStep>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LocationPath {
+    pub(crate) steps: Vec<LocationStep>,
 }
 
-enum Core {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Core {
     Relative(LocationPath),
     Absolute(LocationPath),
 }
 
-impl Core {
-    fn translate(&self, automaton: &mut Automaton, state: State, in_main: bool) {
-        match self {
-            Core::Absolute(location_path) => {
-                if in_main && location_path.steps.is_empty() {
-                    automaton.add(state, Guard::include(NodeType::Document), Formula::Mark);
+// A location path is evaluated in stages. Runs of steps along forward axes
+// are compiled into an automaton, which is run from each node selected so
+// far. A top-down run can't look up or back in the tree, so a step along any
+// other axis is a stage of its own, which walks from the nodes the previous
+// stage selected.
+pub(crate) struct CompiledPath {
+    absolute: bool,
+    stages: Vec<Stage>,
+}
+
+enum Stage {
+    Automaton(Automaton),
+    Walk(Axis, Guard),
+}
+
+// Guards match the node info ids of the document, so a path is compiled for
+// the document it runs on.
+pub(crate) fn compile(core: &Core, document: &Document) -> Result<CompiledPath, XPathError> {
+    let (absolute, location_path) = match core {
+        Core::Absolute(location_path) => (true, location_path),
+        Core::Relative(location_path) => (false, location_path),
+    };
+    let mut stages = Vec::new();
+    let mut forward = Vec::new();
+    for step in &location_path.steps {
+        if step.axis.is_forward() {
+            forward.push(step.clone());
+            continue;
+        }
+        if !forward.is_empty() {
+            stages.push(Stage::Automaton(compile_forward(forward, document)?));
+            forward = Vec::new();
+        }
+        stages.push(Stage::Walk(step.axis, step.guard(document)));
+        let has_target = matches!(step.node_test, NodeTest::ProcessingInstruction(Some(_)));
+        if step.predicate.is_some() || has_target {
+            // what the guard can't test of the nodes walked to is tested by
+            // the automaton of the next stage
+            forward.push(LocationStep {
+                axis: Axis::Self_,
+                node_test: step.node_test.clone(),
+                predicate: step.predicate.clone(),
+            });
+        }
+    }
+    if !forward.is_empty() {
+        stages.push(Stage::Automaton(compile_forward(forward, document)?));
+    }
+    Ok(CompiledPath { absolute, stages })
+}
+
+// Compile forward steps into an automaton that marks the nodes they select.
+fn compile_forward(steps: Vec<LocationStep>, document: &Document) -> Result<Automaton, XPathError> {
+    let mut automaton = Automaton::new();
+    let continuation = LocationPath { steps }.translate(
+        &mut automaton,
+        document,
+        vec![(Guard::all(), Formula::Mark)],
+        false,
+        true,
+    )?;
+    let start_state = automaton.start_state();
+    for (guard, formula) in continuation {
+        automaton.add(start_state, guard, formula);
+    }
+    Ok(automaton)
+}
+
+impl CompiledPath {
+    pub(crate) fn run(&self, document: &Document, node: Node) -> Nodes {
+        let start = if self.absolute { document.root() } else { node };
+        let mut nodes = Nodes::single(start);
+        for stage in &self.stages {
+            nodes = match stage {
+                Stage::Automaton(automaton) => automaton.run_from(document, &nodes),
+                Stage::Walk(axis, guard) => walk(document, *axis, &nodes)
+                    .into_iter()
+                    .filter(|node| guard.matches(document.node_info_id_for_node(*node)))
+                    .collect(),
+            };
+        }
+        nodes
+    }
+}
+
+// the nodes along a reverse axis (or following) of any of the nodes
+fn walk(document: &Document, axis: Axis, nodes: &Nodes) -> Vec<Node> {
+    let is_attribute = |node: Node| document.is_attribute(node) || document.is_namespace(node);
+    match axis {
+        Axis::Parent => nodes
+            .iter()
+            .filter_map(|node| document.parent(node))
+            .collect(),
+        // nodes share the ancestors above the first one they have in
+        // common, so we stop climbing at a node we've already seen
+        Axis::Ancestor => unseen(nodes.iter().map(|node| document.ancestors(node))),
+        Axis::AncestorOrSelf => unseen(nodes.iter().map(|node| document.ancestors_or_self(node))),
+        // the preceding siblings of a node include those of its preceding
+        // siblings, so going from the last node we can stop the same way
+        Axis::PrecedingSibling => unseen(
+            nodes
+                .iter()
+                .rev()
+                .filter(|node| !is_attribute(*node))
+                .map(|node| document.preceding_siblings(node)),
+        ),
+        Axis::Following => {
+            // what follows an attribute is what follows the start of its
+            // element, so its descendants too
+            let starts = nodes.iter().filter_map(|node| {
+                if is_attribute(node) {
+                    let parent = document.parent(node)?;
+                    document
+                        .first_child(parent)
+                        .or_else(|| document.following(parent).next())
                 } else {
-                    let downleft_state = State::new();
-                    automaton.add(
-                        state,
-                        Guard::include(NodeType::Document),
-                        Formula::DownLeft(downleft_state),
-                    );
-                    location_path.translate(automaton, downleft_state, in_main);
+                    document.following(node).next()
                 }
+            });
+            // each following axis runs to the end of the document, so
+            // together they are the one that starts first
+            match starts.min() {
+                Some(start) => std::iter::once(start)
+                    .chain(document.descendants(start))
+                    .chain(document.following(start))
+                    .collect(),
+                None => Vec::new(),
             }
-            _ => unimplemented!(),
+        }
+        Axis::Preceding => {
+            // each preceding axis runs from the start of the document, so
+            // together they are the one of the last node. An attribute has
+            // the same preceding nodes as its element.
+            let last = nodes
+                .iter()
+                .map(|node| {
+                    if is_attribute(node) {
+                        document.parent(node).unwrap_or(node)
+                    } else {
+                        node
+                    }
+                })
+                .max();
+            match last {
+                Some(last) => document.axis_preceding(last).collect(),
+                None => Vec::new(),
+            }
+        }
+        axis => unreachable!("axis {:?} is compiled into an automaton", axis),
+    }
+}
+
+// the nodes of each walk up to the first one an earlier walk went through
+fn unseen<I: Iterator<Item = Node>>(walks: impl Iterator<Item = I>) -> Vec<Node> {
+    let mut seen = HashSet::new();
+    let mut nodes = Vec::new();
+    for walk in walks {
+        for node in walk {
+            if !seen.insert(node) {
+                break;
+            }
+            nodes.push(node);
+        }
+    }
+    nodes
+}
+
+impl Axis {
+    // whether the nodes along the axis are in the subtree of the context
+    // node, or of its following siblings
+    fn is_forward(&self) -> bool {
+        matches!(
+            self,
+            Axis::Attribute
+                | Axis::Child
+                | Axis::Descendant
+                | Axis::DescendantOrSelf
+                | Axis::FollowingSibling
+                | Axis::Namespace
+                | Axis::Self_
+        )
+    }
+}
+
+// The transitions a node takes, each for the nodes its guard matches. As
+// these are alternatives, a continuation holds for a node if any of them
+// does, and they are a disjunction.
+type Continuation = Vec<(Guard, Formula)>;
+
+fn and(left: &Continuation, right: &Continuation) -> Continuation {
+    left.iter()
+        .flat_map(|(left_guard, left_formula)| {
+            right.iter().map(|(right_guard, right_formula)| {
+                (
+                    left_guard.intersection(right_guard),
+                    Formula::and(left_formula.clone(), right_formula.clone()),
+                )
+            })
+        })
+        .filter(|(guard, _)| !guard.is_nothing())
+        .collect()
+}
+
+fn not(continuation: &Continuation) -> Continuation {
+    // each alternative doesn't hold if its guard doesn't match or its
+    // formula is false, and all alternatives must not hold
+    continuation.iter().fold(
+        vec![(Guard::all(), Formula::True)],
+        |result, (guard, formula)| {
+            let alternative = vec![
+                (guard.complement(), Formula::True),
+                (Guard::all(), Formula::not(formula.clone())),
+            ];
+            and(&result, &alternative)
+        },
+    )
+}
+
+// the nodes that hold the attributes and namespaces of an element; they
+// are never selected themselves
+fn holders() -> Vec<NodeInfoId> {
+    vec![NAMESPACES_NODE_INFO_OPEN_ID, ATTRIBUTES_NODE_INFO_OPEN_ID]
+}
+
+// add the transitions of the nodes matched by the guard in a state
+fn add_matches(
+    automaton: &mut Automaton,
+    state: State,
+    guard: &Guard,
+    continuation: &Continuation,
+) {
+    for (continuation_guard, formula) in continuation {
+        automaton.add(
+            state,
+            guard.intersection(continuation_guard),
+            formula.clone(),
+        );
+    }
+}
+
+impl Core {
+    // the transitions for a context node from which the path selects a
+    // node for which the continuation holds
+    fn translate(
+        &self,
+        automaton: &mut Automaton,
+        document: &Document,
+        continuation: Continuation,
+        on_attribute: bool,
+    ) -> Result<Continuation, XPathError> {
+        match self {
+            Core::Relative(location_path) => {
+                location_path.translate(automaton, document, continuation, on_attribute, false)
+            }
+            Core::Absolute(_) => Err(XPathError::Unsupported(
+                "absolute paths in predicates".to_string(),
+            )),
+        }
+    }
+}
+
+impl Pred {
+    // the transitions for a node for which the predicate holds
+    fn translate(
+        &self,
+        automaton: &mut Automaton,
+        document: &Document,
+        on_attribute: bool,
+    ) -> Result<Continuation, XPathError> {
+        let exists = |pred| vec![(Guard::all(), pred)];
+        match self {
+            Pred::And(left, right) => Ok(and(
+                &left.translate(automaton, document, on_attribute)?,
+                &right.translate(automaton, document, on_attribute)?,
+            )),
+            Pred::Or(left, right) => {
+                let mut continuation = left.translate(automaton, document, on_attribute)?;
+                continuation.extend(right.translate(automaton, document, on_attribute)?);
+                Ok(continuation)
+            }
+            Pred::Not(inner) => Ok(not(&inner.translate(automaton, document, on_attribute)?)),
+            Pred::Core(core) => {
+                core.translate(automaton, document, exists(Formula::True), on_attribute)
+            }
+            Pred::Equals(core, value) => core.translate(
+                automaton,
+                document,
+                exists(Formula::Pred(mta::Pred::StringValueEquals(value.clone()))),
+                on_attribute,
+            ),
+            Pred::NotEquals(core, value) => core.translate(
+                automaton,
+                document,
+                exists(Formula::Pred(mta::Pred::StringValueNotEquals(
+                    value.clone(),
+                ))),
+                on_attribute,
+            ),
         }
     }
 }
 
 impl LocationPath {
-    fn translate(&self, automaton: &mut Automaton, state: State, in_main: bool) {
-        debug_assert!(!self.steps.is_empty());
-        let last = self.steps.len() - 1;
-        let mut next_state = state;
-        for (i, step) in self.steps.iter().enumerate() {
-            if i != last {
-                next_state = step.translate(automaton, next_state, false);
-            } else {
-                // translate last step. If we're in main, mark it
-                step.translate(automaton, next_state, in_main);
+    // We go from the last step to the first: each step turns the transitions
+    // for the nodes it selects into the transitions for its context node.
+    //
+    // The steps of the main path select nodes, and the states scanning for
+    // them accept at the end of the tree, so that a run always collects what
+    // was marked. The steps of a predicate only have to exist, so their
+    // states only accept if they find a node.
+    fn translate(
+        &self,
+        automaton: &mut Automaton,
+        document: &Document,
+        mut continuation: Continuation,
+        on_attribute: bool,
+        in_main: bool,
+    ) -> Result<Continuation, XPathError> {
+        let steps = merge_descendant_steps(&self.steps);
+        // whether the context nodes of each step are attributes or namespaces
+        let mut contexts = Vec::with_capacity(steps.len());
+        let mut current = on_attribute;
+        for step in &steps {
+            contexts.push(current);
+            current = step.selects_attributes(current);
+        }
+        for (step, on_attribute) in steps.iter().zip(contexts).rev() {
+            if !step.axis.is_forward() {
+                return Err(XPathError::Unsupported(format!(
+                    "axis {:?} in predicates",
+                    step.axis
+                )));
+            }
+            continuation =
+                step.translate(automaton, document, continuation, on_attribute, in_main)?;
+        }
+        Ok(continuation)
+    }
+}
+
+// `descendant-or-self::node()/child::x` (what `//x` stands for) selects the
+// same nodes as `descendant::x`, as predicates don't depend on position. A
+// single descendant step is cheaper to run, as the automaton can jump in it
+// to the nodes it looks for.
+fn merge_descendant_steps(steps: &[LocationStep]) -> Vec<LocationStep> {
+    let mut merged: Vec<LocationStep> = Vec::with_capacity(steps.len());
+    for step in steps {
+        if let Some(previous) = merged.last_mut() {
+            if previous.axis == Axis::DescendantOrSelf
+                && previous.node_test == NodeTest::Node
+                && previous.predicate.is_none()
+                && step.axis == Axis::Child
+            {
+                *previous = LocationStep {
+                    axis: Axis::Descendant,
+                    node_test: step.node_test.clone(),
+                    predicate: step.predicate.clone(),
+                };
+                continue;
             }
         }
+        merged.push(step.clone());
     }
+    merged
 }
 
 impl LocationStep {
-    fn translate(&self, automaton: &mut Automaton, state: State, mark: bool) -> State {
+    fn selects_attributes(&self, on_attribute: bool) -> bool {
         match self.axis {
+            Axis::Attribute | Axis::Namespace => true,
+            Axis::Self_ | Axis::DescendantOrSelf => on_attribute,
+            _ => false,
+        }
+    }
+
+    fn scan_state(automaton: &mut Automaton, in_main: bool) -> State {
+        let state = State::new();
+        if in_main {
+            automaton.add_bottom_state(state);
+        }
+        state
+    }
+
+    // Given the transitions for the nodes selected by this step, give the
+    // transitions for its context node.
+    fn translate(
+        &self,
+        automaton: &mut Automaton,
+        document: &Document,
+        continuation: Continuation,
+        on_attribute: bool,
+        in_main: bool,
+    ) -> Result<Continuation, XPathError> {
+        if self.axis == Axis::Namespace {
+            // only declarations are namespace nodes in the tree; those an
+            // element inherits, and the xml namespace, have no node to select
+            return Err(XPathError::Unsupported("namespace axis".to_string()));
+        }
+        let guard = self.guard(document);
+        // a predicate is attached to the states of the nodes it tests, and so
+        // is the target of a processing instruction, as the guard can only
+        // test the kind of node
+        let continuation = match &self.predicate {
+            Some(predicate) => and(
+                &predicate.translate(automaton, document, self.selects_attributes(on_attribute))?,
+                &continuation,
+            ),
+            None => continuation,
+        };
+        let continuation = match &self.node_test {
+            NodeTest::ProcessingInstruction(Some(target)) => and(
+                &vec![(
+                    Guard::all(),
+                    Formula::Pred(mta::Pred::ProcessingInstructionTarget(target.clone())),
+                )],
+                &continuation,
+            ),
+            _ => continuation,
+        };
+        Ok(self.translate_axis(
+            automaton,
+            self.axis,
+            &guard,
+            continuation,
+            on_attribute,
+            in_main,
+        ))
+    }
+
+    fn translate_axis(
+        &self,
+        automaton: &mut Automaton,
+        axis: Axis,
+        guard: &Guard,
+        continuation: Continuation,
+        on_attribute: bool,
+        in_main: bool,
+    ) -> Continuation {
+        match axis {
+            Axis::Self_ => continuation
+                .into_iter()
+                .map(|(continuation_guard, formula)| {
+                    (guard.intersection(&continuation_guard), formula)
+                })
+                .filter(|(guard, _)| !guard.is_nothing())
+                .collect(),
             Axis::Child => {
-                let next_state = State::new();
-                let formula =
-                    Formula::and(Formula::DownLeft(next_state), Formula::DownRight(state));
-                let formula = if mark { formula.marking() } else { formula };
-                automaton.add(state, self.guard(), formula);
+                let state = Self::scan_state(automaton, in_main);
+                add_matches(automaton, state, guard, &continuation);
                 automaton.add(state, Guard::all(), Formula::DownRight(state));
-                next_state
+                vec![(Guard::all(), Formula::DownLeft(state))]
             }
             Axis::Descendant => {
-                let next_state = State::new();
-                let formula = Formula::and(
+                let state = Self::scan_state(automaton, in_main);
+                add_matches(automaton, state, guard, &continuation);
+                automaton.add(state, Guard::excludes(holders()), Formula::DownLeft(state));
+                automaton.add(state, Guard::all(), Formula::DownRight(state));
+                vec![(Guard::all(), Formula::DownLeft(state))]
+            }
+            Axis::DescendantOrSelf => {
+                let mut transitions = self.translate_axis(
+                    automaton,
+                    Axis::Self_,
+                    guard,
+                    continuation.clone(),
+                    on_attribute,
+                    in_main,
+                );
+                transitions.extend(self.translate_axis(
+                    automaton,
+                    Axis::Descendant,
+                    guard,
+                    continuation,
+                    on_attribute,
+                    in_main,
+                ));
+                transitions
+            }
+            Axis::FollowingSibling => {
+                if on_attribute {
+                    // attributes and namespaces have no siblings
+                    return Vec::new();
+                }
+                let state = Self::scan_state(automaton, in_main);
+                add_matches(automaton, state, guard, &continuation);
+                automaton.add(state, Guard::all(), Formula::DownRight(state));
+                vec![(Guard::all(), Formula::DownRight(state))]
+            }
+            Axis::Attribute => {
+                // the namespaces node comes before the attributes node
+                let holder_state = State::new();
+                let state = Self::scan_state(automaton, in_main);
+                automaton.add(
+                    holder_state,
+                    Guard::include(ATTRIBUTES_NODE_INFO_OPEN_ID),
                     Formula::DownLeft(state),
-                    Formula::and(Formula::DownLeft(next_state), Formula::DownRight(state)),
                 );
-                let formula = if mark { formula.marking() } else { formula };
-                automaton.add(state, self.guard(), formula);
                 automaton.add(
-                    state,
-                    Guard::all(),
-                    Formula::and(Formula::DownLeft(state), Formula::DownRight(state)),
+                    holder_state,
+                    Guard::include(NAMESPACES_NODE_INFO_OPEN_ID),
+                    Formula::DownRight(holder_state),
                 );
-                next_state
+                add_matches(automaton, state, guard, &continuation);
+                automaton.add(state, Guard::all(), Formula::DownRight(state));
+                vec![(Guard::all(), Formula::DownLeft(holder_state))]
             }
-            _ => unimplemented!(),
+            axis => unreachable!("axis {:?} is walked", axis),
         }
     }
 
-    fn guard(&self) -> Guard {
+    // The guard matches the node info ids in the document that pass the
+    // node test. Names are looked up among the nodes of the principal node
+    // kind of the axis.
+    fn guard(&self, document: &Document) -> Guard {
+        let ids =
+            |matches: &dyn Fn(&NodeType) -> bool| Guard::includes(document.node_info_ids(matches));
         match &self.node_test {
+            // all nodes along this axis are attributes
+            NodeTest::Node if self.axis == Axis::Attribute => Guard::all(),
+            NodeTest::Node => Guard::excludes(holders()),
+            NodeTest::Text => Guard::include(TEXT_NODE_INFO_OPEN_ID),
+            NodeTest::Comment => Guard::include(COMMENT_NODE_INFO_OPEN_ID),
+            NodeTest::ProcessingInstruction(_) => {
+                Guard::include(PROCESSING_INSTRUCTION_NODE_INFO_OPEN_ID)
+            }
             NodeTest::TagName {
-                namespace: _,
+                namespace,
                 local_name,
-            } => {
-                // TODO: namespace and wildcard handling
-                // we construct the matching tag type
-                let node_type = NodeType::Element(NodeName::new(
-                    "",
-                    local_name.as_ref().expect("local name is not wildcard"),
-                ));
-                Guard::include(node_type)
-            }
-            _ => unimplemented!(),
+            } => match self.axis {
+                Axis::Attribute => ids(&|node_type| {
+                    matches!(node_type, NodeType::Attribute(name)
+                        if name_matches(namespace.as_deref(), local_name, name.namespace(), name.local_name()))
+                }),
+                _ => ids(&|node_type| {
+                    matches!(node_type, NodeType::Element(name)
+                        if name_matches(namespace.as_deref(), local_name, name.namespace(), name.local_name()))
+                }),
+            },
         }
     }
 }
 
+// whether a name test matches a name; a test without a namespace or local
+// name is a wildcard for it
+fn name_matches(
+    namespace: Option<&str>,
+    local_name: &Option<String>,
+    name_namespace: &[u8],
+    name_local_name: &[u8],
+) -> bool {
+    namespace.is_none_or(|namespace| namespace.as_bytes() == name_namespace)
+        && local_name
+            .as_ref()
+            .is_none_or(|local_name| local_name.as_bytes() == name_local_name)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         mta::{Formula, Nodes, State},
+        name::NodeName,
         parser::parse_document,
     };
 
@@ -166,7 +664,7 @@ mod tests {
 
         automaton.add(
             q0,
-            Guard::include(NodeType::Document),
+            Guard::include(d.node_info_id(NodeType::Document).unwrap()),
             Formula::DownLeft(q1),
         );
         // down left q1 and down left q2 and down right q1
@@ -176,7 +674,10 @@ mod tests {
         );
         automaton.add(
             q1,
-            Guard::include(NodeType::Element(NodeName::new("", "listitem"))),
+            Guard::include(
+                d.node_info_id(NodeType::Element(NodeName::new("", "listitem")))
+                    .unwrap(),
+            ),
             formula,
         );
         automaton.add(
@@ -191,7 +692,10 @@ mod tests {
         );
         automaton.add(
             q2,
-            Guard::include(NodeType::Element(NodeName::new("", "keyword"))),
+            Guard::include(
+                d.node_info_id(NodeType::Element(NodeName::new("", "keyword")))
+                    .unwrap(),
+            ),
             formula,
         );
         automaton.add(
@@ -201,7 +705,10 @@ mod tests {
         );
         automaton.add(
             q3,
-            Guard::include(NodeType::Element(NodeName::new("", "emph"))),
+            Guard::include(
+                d.node_info_id(NodeType::Element(NodeName::new("", "emph")))
+                    .unwrap(),
+            ),
             Formula::True,
         );
         automaton.add(q3, Guard::all(), Formula::DownRight(q3));
@@ -234,7 +741,7 @@ mod tests {
 
         automaton.add(
             q0,
-            Guard::include(NodeType::Document),
+            Guard::include(d.node_info_id(NodeType::Document).unwrap()),
             Formula::DownLeft(q1),
         );
         // down left q1 and down left q2 and down right q1
@@ -244,7 +751,10 @@ mod tests {
         );
         automaton.add(
             q1,
-            Guard::include(NodeType::Element(NodeName::new("", "listitem"))),
+            Guard::include(
+                d.node_info_id(NodeType::Element(NodeName::new("", "listitem")))
+                    .unwrap(),
+            ),
             formula,
         );
         automaton.add(
@@ -258,7 +768,10 @@ mod tests {
         );
         automaton.add(
             q2,
-            Guard::include(NodeType::Element(NodeName::new("", "keyword"))),
+            Guard::include(
+                d.node_info_id(NodeType::Element(NodeName::new("", "keyword")))
+                    .unwrap(),
+            ),
             formula,
         );
         automaton.add(
@@ -283,11 +796,7 @@ mod tests {
         let doc = parse_document(r#"<doc><a/><b/></doc>"#).unwrap();
         let root = doc.root();
         let path = Core::Absolute(LocationPath { steps: vec![] });
-        let mut automaton = Automaton::new();
-        let start_state = automaton.start_state();
-        path.translate(&mut automaton, start_state, true);
-
-        let marked = automaton.run(&doc, root);
+        let marked = compile(&path, &doc).unwrap().run(&doc, root);
 
         assert_eq!(marked, vec![root].into_iter().collect::<Nodes>());
     }
@@ -322,11 +831,7 @@ mod tests {
             ],
         });
 
-        let mut automaton = Automaton::new();
-        let start_state = automaton.start_state();
-        path.translate(&mut automaton, start_state, true);
-
-        let marked = automaton.run(&d, root);
+        let marked = compile(&path, &d).unwrap().run(&d, root);
 
         assert_eq!(marked, vec![keyword].into_iter().collect::<Nodes>());
     }
@@ -361,12 +866,270 @@ mod tests {
             ],
         });
 
-        let mut automaton = Automaton::new();
-        let start_state = automaton.start_state();
-        path.translate(&mut automaton, start_state, true);
-
-        let marked = automaton.run(&d, root);
+        let marked = compile(&path, &d).unwrap().run(&d, root);
 
         assert_eq!(marked, vec![a1, a2].into_iter().collect::<Nodes>());
     }
+
+    fn select(d: &Document, node: Node, expr: &str) -> Nodes {
+        let mut selected = Nodes::new();
+        for core in crate::xpath::parse(expr, &|_| None).unwrap() {
+            selected.append(compile(&core, d).unwrap().run(d, node));
+        }
+        selected
+    }
+
+    #[test]
+    fn test_self() {
+        let d = parse_document(r#"<doc><a/><b/></doc>"#).unwrap();
+        let doc = d.document_element();
+        let a = d.first_child(doc).unwrap();
+
+        assert_eq!(select(&d, a, "self::a"), Nodes::single(a));
+        assert_eq!(select(&d, a, "self::b"), Nodes::new());
+        assert_eq!(
+            select(&d, d.root(), "/doc/a/self::node()"),
+            Nodes::single(a)
+        );
+    }
+
+    #[test]
+    fn test_following_sibling() {
+        let d = parse_document(r#"<doc><a><b/></a><b/><c/><b/></doc>"#).unwrap();
+        let doc = d.document_element();
+        let a = d.first_child(doc).unwrap();
+        let b1 = d.next_sibling(a).unwrap();
+        let c = d.next_sibling(b1).unwrap();
+        let b2 = d.next_sibling(c).unwrap();
+
+        assert_eq!(
+            select(&d, a, "following-sibling::b"),
+            vec![b1, b2].into_iter().collect::<Nodes>()
+        );
+        assert_eq!(
+            select(&d, d.root(), "/doc/c/following-sibling::node()"),
+            Nodes::single(b2)
+        );
+    }
+
+    #[test]
+    fn test_attribute() {
+        let d = parse_document(r#"<doc xmlns:ex="http://example.com" id="d"><a id="1" ref="x"/><a/><b ex:id="2"/></doc>"#)
+            .unwrap();
+        let doc = d.document_element();
+        let a = d.first_child(doc).unwrap();
+        let id = d.attribute_node(a, "id").unwrap();
+        let reference = d.attribute_node(a, "ref").unwrap();
+
+        assert_eq!(select(&d, d.root(), "//a/@id"), Nodes::single(id));
+        assert_eq!(
+            select(&d, a, "@node()"),
+            vec![id, reference].into_iter().collect::<Nodes>()
+        );
+        // attributes have no siblings
+        assert_eq!(
+            select(&d, d.root(), "//a/@id/following-sibling::node()"),
+            Nodes::new()
+        );
+        assert_eq!(select(&d, id, "following-sibling::node()"), Nodes::new());
+        // attributes are not children
+        assert_eq!(select(&d, a, "node()"), Nodes::new());
+        // namespace nodes are only there where they are declared
+        assert!(matches!(
+            d.select(doc, "namespace::node()"),
+            Err(XPathError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_node_tests() {
+        let d = parse_document(r#"<doc a="1">x<b/><!--y--><?t z?><c b="2"/></doc>"#).unwrap();
+        let doc = d.document_element();
+        let a = d.attribute_node(doc, "a").unwrap();
+        let x = d.first_child(doc).unwrap();
+        let b = d.next_sibling(x).unwrap();
+        let comment = d.next_sibling(b).unwrap();
+        let pi = d.next_sibling(comment).unwrap();
+        let c = d.next_sibling(pi).unwrap();
+        let c_b = d.attribute_node(c, "b").unwrap();
+
+        assert_eq!(
+            select(&d, doc, "*"),
+            vec![b, c].into_iter().collect::<Nodes>()
+        );
+        assert_eq!(
+            select(&d, d.root(), "//@*"),
+            vec![a, c_b].into_iter().collect::<Nodes>()
+        );
+        // a name on the attribute axis only matches attributes
+        assert_eq!(select(&d, d.root(), "//@b"), Nodes::single(c_b));
+        assert_eq!(select(&d, doc, "text()"), Nodes::single(x));
+        assert_eq!(select(&d, doc, "comment()"), Nodes::single(comment));
+        assert_eq!(
+            select(&d, doc, "processing-instruction()"),
+            Nodes::single(pi)
+        );
+        assert_eq!(
+            select(&d, doc, "processing-instruction('t')"),
+            Nodes::single(pi)
+        );
+        assert_eq!(select(&d, doc, "processing-instruction('u')"), Nodes::new());
+        assert_eq!(select(&d, doc, "node()").len(), 5);
+    }
+
+    #[test]
+    fn test_reverse_axes() {
+        let d = parse_document(r#"<doc><a><b><c/></b></a><b/></doc>"#).unwrap();
+        let root = d.root();
+        let doc = d.document_element();
+        let a = d.first_child(doc).unwrap();
+        let b1 = d.first_child(a).unwrap();
+        let c = d.first_child(b1).unwrap();
+        let b2 = d.next_sibling(a).unwrap();
+
+        assert_eq!(select(&d, root, "//c/parent::b"), Nodes::single(b1));
+        assert_eq!(select(&d, root, "//c/.."), Nodes::single(b1));
+        assert_eq!(
+            select(&d, root, "//c/ancestor::node()"),
+            vec![root, doc, a, b1].into_iter().collect::<Nodes>()
+        );
+        assert_eq!(
+            select(&d, root, "//b/ancestor-or-self::b/c"),
+            Nodes::single(c)
+        );
+        assert_eq!(select(&d, b2, "preceding-sibling::a/b"), Nodes::single(b1));
+        assert_eq!(select(&d, b2, "preceding::node()").len(), 3);
+        assert_eq!(select(&d, c, "following::b"), Nodes::single(b2));
+    }
+
+    #[test]
+    fn test_overlapping_contexts() {
+        let d = parse_document(r#"<doc><a><b/><a><b/><c/></a><c/></a><c/></doc>"#).unwrap();
+        let root = d.root();
+        let doc = d.document_element();
+        let a1 = d.first_child(doc).unwrap();
+        let b1 = d.first_child(a1).unwrap();
+        let a2 = d.next_sibling(b1).unwrap();
+        let b2 = d.first_child(a2).unwrap();
+        let c2 = d.next_sibling(b2).unwrap();
+        let c1 = d.next_sibling(a2).unwrap();
+        let c0 = d.next_sibling(a1).unwrap();
+        let nodes = |nodes: Vec<Node>| nodes.into_iter().collect::<Nodes>();
+
+        // the second a is in the subtree of the first
+        assert_eq!(select(&d, root, "//b/..//c"), nodes(vec![c2, c1]));
+        assert_eq!(select(&d, root, "//b/../c"), nodes(vec![c2, c1]));
+        assert_eq!(
+            select(&d, root, "//b/../following-sibling::c"),
+            nodes(vec![c1, c0])
+        );
+        assert_eq!(select(&d, root, "//c/ancestor::a"), nodes(vec![a1, a2]));
+        assert_eq!(
+            select(&d, root, "//c/preceding-sibling::*"),
+            nodes(vec![a1, b1, a2, b2])
+        );
+
+        // attributes are among the nodes an automaton starts at
+        let d = parse_document(r#"<doc><a id="1"><b id="2">1</b></a></doc>"#).unwrap();
+        let root = d.root();
+        let doc = d.document_element();
+        let a = d.first_child(doc).unwrap();
+        let b = d.first_child(a).unwrap();
+        let id = d.attribute_node(a, "id").unwrap();
+        assert_eq!(
+            select(&d, root, "//@id/ancestor-or-self::node()[. = '1']"),
+            nodes(vec![root, doc, a, id, b])
+        );
+    }
+
+    #[test]
+    fn test_predicates() {
+        let d =
+            parse_document(r#"<doc><a type="x"><b/></a><a><c/></a><a type="y"><b/><c/></a></doc>"#)
+                .unwrap();
+        let doc = d.document_element();
+        let a1 = d.first_child(doc).unwrap();
+        let a2 = d.next_sibling(a1).unwrap();
+        let a3 = d.next_sibling(a2).unwrap();
+        let root = d.root();
+        let nodes = |nodes: Vec<Node>| nodes.into_iter().collect::<Nodes>();
+
+        assert_eq!(select(&d, root, "//a[b]"), nodes(vec![a1, a3]));
+        assert_eq!(select(&d, root, "//a[b and c]"), nodes(vec![a3]));
+        assert_eq!(select(&d, root, "//a[b or c]"), nodes(vec![a1, a2, a3]));
+        assert_eq!(select(&d, root, "//a[not(b)]"), nodes(vec![a2]));
+        assert_eq!(select(&d, root, "//a[not(@type)]"), nodes(vec![a2]));
+        assert_eq!(select(&d, root, "//a[b][not(c)]"), nodes(vec![a1]));
+        assert_eq!(select(&d, root, "/doc[a/c]/a[@type]"), nodes(vec![a1, a3]));
+        // predicates on the path of a predicate
+        assert_eq!(select(&d, root, "/doc[a[c][@type]]"), nodes(vec![doc]));
+        assert_eq!(select(&d, root, "/doc[a[c][@type='x']]"), nodes(vec![]));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let d =
+            parse_document(r#"<doc><a type="x">foo</a><a type="y">f<b>o</b>o</a><a>bar</a></doc>"#)
+                .unwrap();
+        let doc = d.document_element();
+        let a1 = d.first_child(doc).unwrap();
+        let a2 = d.next_sibling(a1).unwrap();
+        let a3 = d.next_sibling(a2).unwrap();
+        let root = d.root();
+        let nodes = |nodes: Vec<Node>| nodes.into_iter().collect::<Nodes>();
+
+        assert_eq!(select(&d, root, "//a[@type='x']"), nodes(vec![a1]));
+        assert_eq!(select(&d, root, "//a[@type!='x']"), nodes(vec![a2]));
+        assert_eq!(select(&d, root, "//a[not(@type='x')]"), nodes(vec![a2, a3]));
+        assert_eq!(select(&d, root, "//a[.='foo']"), nodes(vec![a1, a2]));
+        assert_eq!(select(&d, root, "//a['o'=b]"), nodes(vec![a2]));
+        assert_eq!(select(&d, root, "/doc[a='bar']"), nodes(vec![doc]));
+        assert_eq!(
+            select(&d, root, "//a[b='o']/@type"),
+            nodes(vec![d.attribute_node(a2, "type").unwrap()])
+        );
+    }
+
+    #[test]
+    fn test_predicate_on_walk() {
+        let d = parse_document(r#"<doc><a id="1"><b/></a><a><b/></a></doc>"#).unwrap();
+        let doc = d.document_element();
+        let a1 = d.first_child(doc).unwrap();
+
+        assert_eq!(
+            select(&d, d.root(), "//b/parent::a[@id]"),
+            Nodes::single(a1)
+        );
+        assert_eq!(select(&d, d.root(), "//b/parent::a[@id='1']/@id").len(), 1);
+    }
+
+    #[test]
+    fn test_jumping() {
+        let d = parse_document(
+            r#"<doc><figure><caption>1</caption><figure><p><caption>2</caption></p></figure></figure><p><caption>3</caption></p><figure><caption>4</caption><note/></figure></doc>"#,
+        )
+        .unwrap();
+        let values = |expr| {
+            select(&d, d.root(), expr)
+                .into_iter()
+                .map(|node| d.string_value(node))
+                .collect::<Vec<_>>()
+        };
+
+        // both descendant steps are states the run jumps in
+        let path = crate::xpath::parse("//figure//caption", &|_| None).unwrap();
+        let compiled = compile(&path[0], &d).unwrap();
+        let Stage::Automaton(automaton) = &compiled.stages[0] else {
+            panic!("expected an automaton");
+        };
+        assert_eq!(automaton.jump_states(&d), 2);
+
+        assert_eq!(values("//figure//caption"), vec!["1", "2", "4"]);
+        assert_eq!(values("//figure/caption"), vec!["1", "4"]);
+        assert_eq!(values("//p//caption"), vec!["2", "3"]);
+        assert_eq!(values("//figure[.//p]/caption"), vec!["1"]);
+        assert_eq!(values("//figure[not(.//p)]//caption"), vec!["4"]);
+        assert_eq!(values("//caption[following-sibling::note]"), vec!["4"]);
+        assert_eq!(values("//figure//table"), Vec::<String>::new());
+    }
 }
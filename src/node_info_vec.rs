@@ -3,7 +3,7 @@ use vers_vecs::{BitVec, SparseRSVec, WaveletMatrix};
 
 use crate::error::Error;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) struct NodeInfoId(u64);
 
 // we ensure we always register these first in any document
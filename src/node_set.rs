@@ -0,0 +1,382 @@
+use std::collections::VecDeque;
+
+use crate::xozdata::Node;
+
+// A set of values kept sorted and without duplicates in a double-ended
+// queue. Because both sides of a set operation are sorted, union,
+// intersection and difference are a single merge-join over them. The queue
+// lets us add values that come before or after all others at either end, so
+// that combining a set with one that lies entirely before or after it only
+// costs the size of the smaller one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct SortedSet<T> {
+    values: VecDeque<T>,
+}
+
+impl<T> Default for SortedSet<T> {
+    fn default() -> Self {
+        Self {
+            values: VecDeque::new(),
+        }
+    }
+}
+
+impl<T: Ord + Copy> SortedSet<T> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn single(value: T) -> Self {
+        Self {
+            values: VecDeque::from([value]),
+        }
+    }
+
+    // this is linear in the size of the set, as the values after it have to
+    // move
+    pub(crate) fn insert(&mut self, value: T) -> bool {
+        match self.values.binary_search(&value) {
+            Ok(_) => false,
+            Err(i) => {
+                self.values.insert(i, value);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, value: &T) -> bool {
+        self.values.binary_search(value).is_ok()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub(crate) fn first(&self) -> Option<T> {
+        self.values.front().copied()
+    }
+
+    pub(crate) fn last(&self) -> Option<T> {
+        self.values.back().copied()
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = T> + ExactSizeIterator + '_ {
+        self.values.iter().copied()
+    }
+
+    // Add the values of the other set in place. When all values of one set
+    // come before those of the other, we move the values of the smaller set
+    // onto the end of the bigger one, so that adding up the results of
+    // subtrees takes time linear in the size of the result. Otherwise we
+    // merge.
+    pub(crate) fn append(&mut self, mut other: Self) {
+        if self.len() < other.len() {
+            std::mem::swap(self, &mut other);
+        }
+        // self can only be empty if other is too
+        let (Some(other_first), Some(other_last)) = (other.first(), other.last()) else {
+            return;
+        };
+        if Some(other_last) < self.first() {
+            for value in other.values.into_iter().rev() {
+                self.values.push_front(value);
+            }
+        } else if Some(other_first) > self.last() {
+            self.values.extend(other.values);
+        } else {
+            *self = self.union(&other);
+        }
+    }
+
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        let (a, b) = (&self.values, &other.values);
+        let mut values = VecDeque::with_capacity(a.len() + b.len());
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                std::cmp::Ordering::Less => {
+                    values.push_back(a[i]);
+                    i += 1;
+                }
+                std::cmp::Ordering::Greater => {
+                    values.push_back(b[j]);
+                    j += 1;
+                }
+                std::cmp::Ordering::Equal => {
+                    values.push_back(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        values.extend(a.range(i..));
+        values.extend(b.range(j..));
+        Self { values }
+    }
+
+    pub(crate) fn intersection(&self, other: &Self) -> Self {
+        let (a, b) = (&self.values, &other.values);
+        let mut values = VecDeque::new();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    values.push_back(a[i]);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        Self { values }
+    }
+
+    pub(crate) fn difference(&self, other: &Self) -> Self {
+        let (a, b) = (&self.values, &other.values);
+        let mut values = VecDeque::new();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() {
+            if j == b.len() {
+                values.extend(a.range(i..));
+                break;
+            }
+            match a[i].cmp(&b[j]) {
+                std::cmp::Ordering::Less => {
+                    values.push_back(a[i]);
+                    i += 1;
+                }
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        Self { values }
+    }
+}
+
+impl<T: Ord + Copy> FromIterator<T> for SortedSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut values = iter.into_iter().collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        Self {
+            values: values.into(),
+        }
+    }
+}
+
+impl<T> IntoIterator for SortedSet<T> {
+    type Item = T;
+    type IntoIter = std::collections::vec_deque::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+/// A set of nodes in document order, without duplicates.
+///
+/// Nodes are ordered the same way as by [`Xoz::sort_key`](crate::Xoz::sort_key):
+/// nodes of a document come before those of documents parsed after it, and
+/// within a document nodes are in preorder. Iterating over a node set gives
+/// its nodes in that order.
+///
+/// Because the nodes are kept sorted, [`NodeSet::union`],
+/// [`NodeSet::intersection`] and [`NodeSet::difference`] take time linear in
+/// the size of both sets.
+///
+/// ```rust
+/// use xoz::{Xoz, NodeSet};
+/// let mut xoz = Xoz::new();
+/// let root = xoz.parse_str("<p><a/><b/><c/></p>").unwrap();
+/// let p = xoz.document_element(root);
+/// let a = xoz.first_child(p).unwrap();
+/// let b = xoz.next_sibling(a).unwrap();
+/// let c = xoz.next_sibling(b).unwrap();
+///
+/// let first: NodeSet = [c, a, c].into_iter().collect();
+/// let second: NodeSet = [b, c].into_iter().collect();
+/// assert_eq!(first.iter().collect::<Vec<_>>(), vec![a, c]);
+///
+/// assert_eq!(first.union(&second).iter().collect::<Vec<_>>(), vec![a, b, c]);
+/// assert_eq!(first.intersection(&second).iter().collect::<Vec<_>>(), vec![c]);
+/// assert_eq!(first.difference(&second).iter().collect::<Vec<_>>(), vec![a]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NodeSet {
+    nodes: SortedSet<Node>,
+}
+
+impl NodeSet {
+    /// Create an empty node set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node to the set.
+    ///
+    /// Returns `true` if the node wasn't in the set yet.
+    ///
+    /// This takes time linear in the size of the set, as the nodes after the
+    /// inserted node move up. To build a set from many nodes, collect them
+    /// into it instead.
+    pub fn insert(&mut self, node: Node) -> bool {
+        self.nodes.insert(node)
+    }
+
+    /// Whether the node is in the set.
+    pub fn contains(&self, node: Node) -> bool {
+        self.nodes.contains(&node)
+    }
+
+    /// The amount of nodes in the set.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the set has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The first node in document order, if any.
+    pub fn first(&self) -> Option<Node> {
+        self.nodes.first()
+    }
+
+    /// The last node in document order, if any.
+    pub fn last(&self) -> Option<Node> {
+        self.nodes.last()
+    }
+
+    /// Iterate over the nodes in document order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Node> + ExactSizeIterator + '_ {
+        self.nodes.iter()
+    }
+
+    /// The nodes in either set.
+    pub fn union(&self, other: &NodeSet) -> NodeSet {
+        NodeSet {
+            nodes: self.nodes.union(&other.nodes),
+        }
+    }
+
+    /// The nodes in both sets.
+    pub fn intersection(&self, other: &NodeSet) -> NodeSet {
+        NodeSet {
+            nodes: self.nodes.intersection(&other.nodes),
+        }
+    }
+
+    /// The nodes in this set that are not in the other set.
+    ///
+    /// This is the XPath `except` operator.
+    pub fn difference(&self, other: &NodeSet) -> NodeSet {
+        NodeSet {
+            nodes: self.nodes.difference(&other.nodes),
+        }
+    }
+}
+
+impl FromIterator<Node> for NodeSet {
+    fn from_iter<I: IntoIterator<Item = Node>>(iter: I) -> Self {
+        Self {
+            nodes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for NodeSet {
+    type Item = Node;
+    type IntoIter = std::collections::vec_deque::IntoIter<Node>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(values: &[usize]) -> SortedSet<usize> {
+        values.iter().copied().collect()
+    }
+
+    fn values(set: SortedSet<usize>) -> Vec<usize> {
+        set.into_iter().collect()
+    }
+
+    #[test]
+    fn test_from_iter_sorts_and_dedups() {
+        assert_eq!(values(set(&[3, 1, 3, 2, 1])), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut s = set(&[1, 5]);
+        assert!(s.insert(3));
+        assert!(!s.insert(5));
+        assert_eq!(values(s.clone()), &[1, 3, 5]);
+        assert!(s.contains(&3));
+        assert!(!s.contains(&4));
+    }
+
+    #[test]
+    fn test_append() {
+        // before and after, in either order of size
+        let mut s = set(&[4, 5]);
+        s.append(set(&[1, 2, 3]));
+        assert_eq!(values(s.clone()), &[1, 2, 3, 4, 5]);
+        s.append(set(&[6]));
+        assert_eq!(values(s.clone()), &[1, 2, 3, 4, 5, 6]);
+        let mut s = set(&[1]);
+        s.append(set(&[2, 3]));
+        assert_eq!(values(s), &[1, 2, 3]);
+        // overlapping
+        let mut s = set(&[1, 3, 5]);
+        s.append(set(&[2, 3, 6]));
+        assert_eq!(values(s), &[1, 2, 3, 5, 6]);
+        // empty
+        let mut s = set(&[]);
+        s.append(set(&[1]));
+        assert_eq!(values(s.clone()), &[1]);
+        s.append(set(&[]));
+        assert_eq!(values(s), &[1]);
+    }
+
+    #[test]
+    fn test_union() {
+        assert_eq!(
+            values(set(&[1, 3, 5]).union(&set(&[2, 3, 6]))),
+            &[1, 2, 3, 5, 6]
+        );
+        assert_eq!(values(set(&[]).union(&set(&[2]))), &[2]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(
+            values(set(&[1, 3, 5, 7]).intersection(&set(&[3, 4, 7]))),
+            &[3, 7]
+        );
+        assert!(set(&[1]).intersection(&set(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_difference() {
+        assert_eq!(
+            values(set(&[1, 3, 5, 7]).difference(&set(&[3, 4, 7]))),
+            &[1, 5]
+        );
+        assert_eq!(values(set(&[1, 2]).difference(&set(&[]))), &[1, 2]);
+        assert!(set(&[]).difference(&set(&[1])).is_empty());
+    }
+}
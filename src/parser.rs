@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 use quick_xml::events::attributes::Attributes;
 use quick_xml::events::Event;
 use quick_xml::name::{LocalName, PrefixDeclaration, ResolveResult};
@@ -8,20 +10,24 @@ use crate::error::quickxml::{Error, NamespaceError, Result};
 use crate::name::NodeName;
 use crate::node_info_vec::SArrayMatrix;
 use crate::structure::Structure;
-use crate::text::TextBuilder;
+use crate::text::{TextBuilder, TextStorage};
 use crate::tree_builder::TreeBuilder;
 use crate::{Namespace, NodeType};
 
 #[cfg(test)]
 pub(crate) fn parse_document(xml: &str) -> Result<Document> {
-    parse_document_with_id(DocumentId::new(0), xml)
+    parse_document_with_id(DocumentId::new(0), xml, TextStorage::Plain)
 }
 
-pub(crate) fn parse_document_with_id(id: DocumentId, xml: &str) -> Result<Document> {
+pub(crate) fn parse_document_with_id(
+    id: DocumentId,
+    xml: &str,
+    text_storage: TextStorage,
+) -> Result<Document> {
     let mut reader = NsReader::from_str(xml);
     reader.config_mut().enable_all_checks(true);
     let mut tree_builder = TreeBuilder::new();
-    let mut text_builder = TextBuilder::new();
+    let mut text_builder = TextBuilder::with_storage(text_storage);
     tree_builder.open(NodeType::Document);
     loop {
         match reader.read_event() {
@@ -100,6 +106,9 @@ pub(crate) fn parse_document_with_id(id: DocumentId, xml: &str) -> Result<Docume
         id,
         structure,
         text_usage,
+        word_index: OnceLock::new(),
+        text_offsets: OnceLock::new(),
+        child_indexes: OnceLock::new(),
     })
 }
 
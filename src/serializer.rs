@@ -33,6 +33,17 @@ impl<'a, W: io::Write> Serializer<'a, W> {
         let mut xmlns_scratch_buf = Vec::with_capacity(64);
         let mut attribute_name_scratch_buf = Vec::with_capacity(64);
 
+        // a node within a document can use namespaces declared on its
+        // ancestors, so we start out with those in scope, and declare them on
+        // the node itself if it's an element
+        let top = node;
+        let inherited = self.doc.inherited_prefixes(top);
+        if let Some(parent) = self.doc.parent(top) {
+            for (prefix, uri) in self.doc.namespaces_in_scope(parent) {
+                self.ns.add_namespace(prefix, uri);
+            }
+        }
+
         for (node_type, tag_state, node) in self.doc.traverse(node) {
             match node_type {
                 NodeType::Document => {
@@ -52,6 +63,7 @@ impl<'a, W: io::Write> Serializer<'a, W> {
                             let elem = self.create_elem(
                                 qname,
                                 node,
+                                if node == top { &inherited } else { &[] },
                                 &mut xmlns_scratch_buf,
                                 &mut attribute_name_scratch_buf,
                             );
@@ -66,6 +78,7 @@ impl<'a, W: io::Write> Serializer<'a, W> {
                             let elem = self.create_elem(
                                 qname,
                                 node,
+                                if node == top { &inherited } else { &[] },
                                 &mut xmlns_scratch_buf,
                                 &mut attribute_name_scratch_buf,
                             );
@@ -75,20 +88,18 @@ impl<'a, W: io::Write> Serializer<'a, W> {
                     }
                 }
                 NodeType::Comment => {
-                    let text = self.doc.comment_str(node).expect("Must be comment node");
+                    let text = self.doc.node_text(node).expect("Must be comment node");
                     self.writer
-                        .write_event(Event::Comment(BytesText::new(text)))?;
+                        .write_event(Event::Comment(BytesText::new(&text)))?;
                 }
                 NodeType::ProcessingInstruction => {
-                    let text = self
-                        .doc
-                        .processing_instruction_str(node)
-                        .expect("Must be PI node");
+                    let text = self.doc.node_text(node).expect("Must be PI node");
                     self.writer.write_event(Event::PI(BytesPI::new(text)))?;
                 }
                 NodeType::Text => {
-                    let text = self.doc.text_str(node).expect("Must be text node");
-                    self.writer.write_event(Event::Text(BytesText::new(text)))?;
+                    let text = self.doc.node_text(node).expect("Must be text node");
+                    self.writer
+                        .write_event(Event::Text(BytesText::new(&text)))?;
                 }
                 NodeType::Attributes
                 | NodeType::Namespaces
@@ -106,12 +117,17 @@ impl<'a, W: io::Write> Serializer<'a, W> {
         &self,
         qname: QName<'a>,
         node: crate::document::Node,
+        inherited: &[(&[u8], &[u8])],
         xmlns_scratch_buf: &mut Vec<u8>,
         attribute_name_scratch_buf: &mut Vec<u8>,
     ) -> BytesStart<'a> {
         let mut elem: BytesStart = qname.into();
 
-        for (prefix, uri) in self.doc.namespace_entries(node) {
+        for (prefix, uri) in inherited
+            .iter()
+            .copied()
+            .chain(self.doc.namespace_entries(node))
+        {
             let key = if prefix.is_empty() {
                 QName(b"xmlns")
             } else {
@@ -126,7 +142,7 @@ impl<'a, W: io::Write> Serializer<'a, W> {
             });
         }
 
-        for (name, value) in self.doc.attribute_entries(node) {
+        for (name, value) in self.doc.attribute_texts(node) {
             elem.push_attribute(Attribute {
                 key: self.ns.qname(name, attribute_name_scratch_buf),
                 value: value.as_bytes().into(),
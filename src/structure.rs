@@ -2,7 +2,7 @@ use std::ops::Range;
 
 use vers_vecs::{
     trees::{bp::BpTree, Tree},
-    RsVec, SubtreeSize,
+    LevelTree, RsVec, SubtreeSize,
 };
 
 use crate::{
@@ -58,6 +58,22 @@ impl<T: NodeInfoVec> Structure<T> {
         self.lookup_node_info_id(&node_info)
     }
 
+    /// Return the ids of all opening node infos with a node type that matches.
+    ///
+    /// This looks at each distinct node info once, not at the nodes.
+    pub(crate) fn lookup_node_info_ids(
+        &self,
+        matches: impl Fn(&NodeType) -> bool,
+    ) -> Vec<NodeInfoId> {
+        self.node_info_lookup
+            .node_infos
+            .iter()
+            .enumerate()
+            .filter(|(_, node_info)| node_info.is_open_tag() && matches(node_info.node_type()))
+            .map(|(i, _)| NodeInfoId::new(i as u64))
+            .collect()
+    }
+
     /// Given a node info id, return the node info.
     ///
     /// Should always succeed given a valid node info.
@@ -86,26 +102,21 @@ impl<T: NodeInfoVec> Structure<T> {
         TextId::new(text_id)
     }
 
-    // paper calls this xml id text
-    // TODO: write a test for this inverse operation
-    #[allow(dead_code)]
+    // get the location of the node with this text id. This is the inverse of
+    // text_id. paper calls this xml id text
     pub(crate) fn text_index(&self, text_id: TextId) -> usize {
-        // TODO: is node_index really needed? don't we get the index if we simply do select?
-        self.tree()
-            .node_index(self.text_opening_parens.select1(text_id.id()))
+        self.text_opening_parens.select1(text_id.id())
     }
 
-    #[allow(dead_code)]
     pub(crate) fn leaf_number(&self, i: usize) -> usize {
         self.text_opening_parens.rank1(i)
     }
 
-    // TODO: write tests
-    #[allow(dead_code)]
+    // the text ids of all text content within the subtree of i, including i
+    // itself
     pub(crate) fn text_ids(&self, i: usize) -> Range<usize> {
-        // TODO: what if i is 0, the root
-        let start = self.leaf_number(i - 1) + 1;
-        let end = self.leaf_number(self.tree.close(i).unwrap());
+        let start = self.leaf_number(i);
+        let end = self.leaf_number(self.tree.close(i).expect("Tree cannot be unbalanced"));
         start..end
     }
 
@@ -155,6 +166,12 @@ impl<T: NodeInfoVec> Structure<T> {
         }
     }
 
+    // The first node labeled tag with preorder larger than that of node i,
+    // whether in the subtree of i or not.
+    pub(crate) fn typed_next(&self, i: usize, node_info_id: NodeInfoId) -> Option<usize> {
+        self.select_node_info_id(self.rank_node_info_id(i + 1, node_info_id)?, node_info_id)
+    }
+
     // The first node labeled tag with preorder larger than that of node i,
     // and not in the subtree of i. Returns None if there is no such node
     // NOTE: the "Fast in-memory XPath search using compressed trees" has a different
@@ -166,20 +183,114 @@ impl<T: NodeInfoVec> Structure<T> {
         )
     }
 
-    // TODO: write tests, wire up to iterator
-    #[allow(dead_code)]
+    // The first child of node i labeled tag. Returns None if there is no
+    // such child.
+    pub(crate) fn typed_child(&self, i: usize, node_info_id: NodeInfoId) -> Option<usize> {
+        self.next_typed_child(i, self.typed_descendant(i, node_info_id), node_info_id)
+    }
+
+    // The first following sibling of node i labeled tag. Returns None if
+    // there is no such sibling.
     pub(crate) fn typed_following_sibling(
         &self,
         i: usize,
         node_info_id: NodeInfoId,
     ) -> Option<usize> {
-        // TODO: is there a smarter way?
-        let sibling = self.typed_following(i, node_info_id)?;
-        if self.tree.parent(i)? == self.tree.parent(sibling)? {
-            Some(sibling)
-        } else {
-            None
+        let parent = self.tree.parent(i)?;
+        self.next_typed_child(parent, self.typed_following(i, node_info_id), node_info_id)
+    }
+
+    // Given a candidate labeled tag, find the first child of parent labeled
+    // tag from there. If the candidate is deeper in the subtree of a child,
+    // we jump past the subtree of that child, so we never visit the
+    // nodes in between.
+    fn next_typed_child(
+        &self,
+        parent: usize,
+        candidate: Option<usize>,
+        node_info_id: NodeInfoId,
+    ) -> Option<usize> {
+        let close = self.tree.close(parent)?;
+        let mut candidate = candidate?;
+        while candidate < close {
+            let child = self.child_containing(parent, candidate);
+            if child == candidate {
+                return Some(candidate);
+            }
+            candidate = self.typed_following(child, node_info_id)?;
         }
+        None
+    }
+
+    // The last node labeled tag before node i in preorder that is not an
+    // ancestor of i. Returns None if there is no such node.
+    pub(crate) fn typed_preceding(&self, i: usize, node_info_id: NodeInfoId) -> Option<usize> {
+        let mut rank = self.rank_node_info_id(i, node_info_id)?;
+        while rank > 0 {
+            let candidate = self.select_node_info_id(rank - 1, node_info_id)?;
+            if self.tree.close(candidate)? < i {
+                return Some(candidate);
+            }
+            // the candidate encloses i, so it's an ancestor; try the one
+            // before it
+            rank -= 1;
+        }
+        None
+    }
+
+    // The last preceding sibling of node i labeled tag. Returns None if there
+    // is no such sibling.
+    pub(crate) fn typed_preceding_sibling(
+        &self,
+        i: usize,
+        node_info_id: NodeInfoId,
+    ) -> Option<usize> {
+        let parent = self.tree.parent(i)?;
+        let mut candidate = self.typed_preceding(i, node_info_id)?;
+        while candidate > parent {
+            let child = self.child_containing(parent, candidate);
+            // going backwards, we reach the candidate before the child that
+            // contains it, so the child may be labeled tag itself
+            if child == candidate || self.node_info_id(child) == node_info_id {
+                return Some(child);
+            }
+            candidate = self.typed_preceding(child, node_info_id)?;
+        }
+        None
+    }
+
+    // The nearest ancestor of node i labeled tag. Returns None if there is no
+    // such ancestor.
+    pub(crate) fn typed_ancestor(&self, i: usize, node_info_id: NodeInfoId) -> Option<usize> {
+        // an opening tag before i that isn't closed before i encloses i. If
+        // all opening tags are closed there is no such ancestor, and we don't
+        // have to go up the tree at all.
+        let close_node_info_id = self.lookup_node_info_id(&NodeInfo::close(
+            self.lookup_node_info(node_info_id).node_type().clone(),
+        ))?;
+        if self.rank_node_info_id(i, node_info_id)?
+            == self.rank_node_info_id(i, close_node_info_id)?
+        {
+            return None;
+        }
+        let mut node = i;
+        loop {
+            node = self.tree.enclose(node)?;
+            if self.node_info_id(node) == node_info_id {
+                return Some(node);
+            }
+        }
+    }
+
+    // The child of ancestor that contains descendant
+    fn child_containing(&self, ancestor: usize, descendant: usize) -> usize {
+        let levels = self.tree.depth(descendant) - self.tree.depth(ancestor) - 1;
+        if levels == 0 {
+            return descendant;
+        }
+        self.tree
+            .level_ancestor(descendant, levels)
+            .expect("Descendant should be within ancestor")
     }
 }
 
@@ -306,5 +417,112 @@ mod tests {
 
         assert_eq!(structure.text_id(2).id(), 0);
         assert_eq!(structure.text_id(6).id(), 1);
+
+        assert_eq!(structure.text_index(TextId::new(0)), 2);
+        assert_eq!(structure.text_index(TextId::new(1)), 6);
+
+        assert_eq!(structure.text_ids(0), 0..2);
+        assert_eq!(structure.text_ids(1), 0..1);
+        assert_eq!(structure.text_ids(2), 0..1);
+        assert_eq!(structure.text_ids(5), 1..2);
+    }
+
+    #[test]
+    fn test_typed_child_and_following_sibling() {
+        // <doc><row/><x><row/></x><row/></doc>
+        let mut builder = TreeBuilder::new();
+        let doc = NodeType::Element(NodeName::new("", "doc"));
+        let row = NodeType::Element(NodeName::new("", "row"));
+        let x = NodeType::Element(NodeName::new("", "x"));
+        // 0
+        builder.open(doc.clone());
+        // 1, 2
+        builder.open(row.clone());
+        builder.close(row.clone());
+        // 3
+        builder.open(x.clone());
+        // 4, 5
+        builder.open(row.clone());
+        builder.close(row.clone());
+        // 6
+        builder.close(x.clone());
+        // 7, 8
+        builder.open(row.clone());
+        builder.close(row.clone());
+        // 9
+        builder.close(doc.clone());
+
+        let structure = Structure::new(builder, |builder| {
+            SArrayMatrix::new(builder.usage(), builder.node_info_amount())
+        })
+        .unwrap();
+        let row_id = structure.lookup_node_info_id_for_node_type(row).unwrap();
+        let x_id = structure.lookup_node_info_id_for_node_type(x).unwrap();
+
+        assert_eq!(structure.typed_child(0, row_id), Some(1));
+        assert_eq!(structure.typed_child(0, x_id), Some(3));
+        assert_eq!(structure.typed_child(3, row_id), Some(4));
+        assert_eq!(structure.typed_child(1, row_id), None);
+        // the row within x is skipped
+        assert_eq!(structure.typed_following_sibling(1, row_id), Some(7));
+        assert_eq!(structure.typed_following_sibling(7, row_id), None);
+        assert_eq!(structure.typed_following_sibling(4, row_id), None);
+        assert_eq!(structure.typed_following_sibling(1, x_id), Some(3));
+    }
+
+    #[test]
+    fn test_typed_preceding_and_ancestor() {
+        // <doc><a/><a><b/><a/></a><b/></doc>
+        let mut builder = TreeBuilder::new();
+        let doc = NodeType::Element(NodeName::new("", "doc"));
+        let a = NodeType::Element(NodeName::new("", "a"));
+        let b = NodeType::Element(NodeName::new("", "b"));
+        // 0
+        builder.open(doc.clone());
+        // 1, 2
+        builder.open(a.clone());
+        builder.close(a.clone());
+        // 3
+        builder.open(a.clone());
+        // 4, 5
+        builder.open(b.clone());
+        builder.close(b.clone());
+        // 6, 7
+        builder.open(a.clone());
+        builder.close(a.clone());
+        // 8
+        builder.close(a.clone());
+        // 9, 10
+        builder.open(b.clone());
+        builder.close(b.clone());
+        // 11
+        builder.close(doc.clone());
+
+        let structure = Structure::new(builder, |builder| {
+            SArrayMatrix::new(builder.usage(), builder.node_info_amount())
+        })
+        .unwrap();
+        let doc_id = structure.lookup_node_info_id_for_node_type(doc).unwrap();
+        let a_id = structure.lookup_node_info_id_for_node_type(a).unwrap();
+        let b_id = structure.lookup_node_info_id_for_node_type(b).unwrap();
+
+        // the a at 3 is an ancestor, so it's skipped
+        assert_eq!(structure.typed_preceding(6, a_id), Some(1));
+        assert_eq!(structure.typed_preceding(9, a_id), Some(6));
+        assert_eq!(structure.typed_preceding(9, b_id), Some(4));
+        assert_eq!(structure.typed_preceding(1, a_id), None);
+
+        assert_eq!(structure.typed_preceding_sibling(9, a_id), Some(3));
+        assert_eq!(structure.typed_preceding_sibling(3, a_id), Some(1));
+        // the b at 4 is nested in a sibling
+        assert_eq!(structure.typed_preceding_sibling(9, b_id), None);
+        assert_eq!(structure.typed_preceding_sibling(6, b_id), Some(4));
+        assert_eq!(structure.typed_preceding_sibling(4, a_id), None);
+
+        assert_eq!(structure.typed_ancestor(6, a_id), Some(3));
+        assert_eq!(structure.typed_ancestor(4, doc_id), Some(0));
+        assert_eq!(structure.typed_ancestor(9, a_id), None);
+        assert_eq!(structure.typed_ancestor(3, a_id), None);
+        assert_eq!(structure.typed_ancestor(6, b_id), None);
     }
 }
@@ -1,22 +1,90 @@
-use std::ops::Range;
+use std::{borrow::Cow, ops::Range};
 
+use ahash::{HashMap, HashMapExt};
+use sucds::{int_vectors::CompactVector, Serializable};
 use vers_vecs::SparseRSVec;
 
+#[cfg(doc)]
+use crate::Xoz;
+use crate::{compressed_text::CompressedText, document::DocumentId};
+
+/// How the text content of a document is stored.
+///
+/// Text content is the text of text nodes, attribute values, comments and
+/// processing instructions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TextStorage {
+    /// All text is stored uncompressed. This is the fastest to access.
+    #[default]
+    Plain,
+    /// Text is stored in compressed blocks. A block is decompressed when
+    /// text in it is accessed, and the few blocks used last are kept in a
+    /// cache. As the cache can drop a block again, text can't be borrowed:
+    /// use [`Xoz::node_text`] and [`Xoz::attribute_text`], which give a copy,
+    /// rather than the functions that give `&str`.
+    ///
+    /// This uses a lot less memory for text-heavy documents, at the cost of
+    /// slower text access. The cache can be cleared with
+    /// [`Xoz::clear_text_cache`].
+    Compressed,
+    /// Equal text values are stored only once, and each text refers to its
+    /// distinct value.
+    ///
+    /// This saves memory for documents where the same values occur many
+    /// times, such as data documents with repeated attribute values. It also
+    /// allows comparing values by [`ValueId`] without accessing the text.
+    Deduplicated,
+}
+
+/// The identity of a distinct text value in a document.
+///
+/// Obtained with [`Xoz::value_id`] for documents parsed with
+/// [`TextStorage::Deduplicated`]. Within a document, two nodes have the same
+/// value id if and only if they have the same text value. Value ids from
+/// different documents are never equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValueId {
+    pub(crate) document_id: DocumentId,
+    pub(crate) id: usize,
+}
+
 pub(crate) struct TextBuilder {
     s: String,
     positions: Vec<u64>,
+    storage: TextStorage,
+    // with deduplication, the entry for each distinct value
+    values: HashMap<String, u64>,
+    // with deduplication, the entry for each text
+    value_ids: Vec<u64>,
     // bitmap: BitVec,
 }
 
 impl TextBuilder {
+    #[cfg(test)]
     pub(crate) fn new() -> Self {
+        Self::with_storage(TextStorage::Plain)
+    }
+
+    pub(crate) fn with_storage(storage: TextStorage) -> Self {
         Self {
             s: String::new(),
             positions: Vec::new(), // bitmap: BitVec::new(),
+            storage,
+            values: HashMap::new(),
+            value_ids: Vec::new(),
         }
     }
 
     pub(crate) fn text_node(&mut self, text: &str) {
+        if self.storage == TextStorage::Deduplicated {
+            if let Some(value_id) = self.values.get(text) {
+                self.value_ids.push(*value_id);
+                return;
+            }
+            let value_id = self.positions.len() as u64;
+            self.values.insert(text.to_string(), value_id);
+            self.value_ids.push(value_id);
+        }
         self.s.push_str(text);
         // terminator $, the 0 byte
         let position = self.s.len() as u64;
@@ -25,9 +93,23 @@ impl TextBuilder {
     }
 
     pub(crate) fn build(self) -> TextUsage {
+        let sarray = SparseRSVec::new(&self.positions, self.s.len() as u64);
+        let value_ids = if self.storage == TextStorage::Deduplicated {
+            // we can unwrap as we know that u64 can be converted to usize
+            Some(CompactVector::from_slice(&self.value_ids).unwrap())
+        } else {
+            None
+        };
+        let text = match self.storage {
+            TextStorage::Plain | TextStorage::Deduplicated => Text::Plain(self.s),
+            TextStorage::Compressed => {
+                Text::Compressed(CompressedText::new(&self.s, &self.positions))
+            }
+        };
         TextUsage {
-            sarray: SparseRSVec::new(&self.positions, self.s.len() as u64),
-            text: self.s,
+            text,
+            sarray,
+            value_ids,
         }
     }
 }
@@ -45,15 +127,72 @@ impl TextId {
     }
 }
 
+// text borrowed from plain storage. Compressed text is only decompressed
+// into a cache that can drop it again, so it can't be borrowed.
+pub(crate) fn borrowed(text: Cow<'_, str>) -> &str {
+    match text {
+        Cow::Borrowed(text) => text,
+        Cow::Owned(_) => {
+            panic!("Compressed text cannot be borrowed, use Xoz::node_text instead")
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Text {
+    Plain(String),
+    Compressed(CompressedText),
+}
+
+impl Text {
+    fn heap_size(&self) -> usize {
+        match self {
+            Text::Plain(text) => text.len(),
+            Text::Compressed(text) => text.heap_size(),
+        }
+    }
+
+    // compressed text is decompressed into a cache that can drop it again,
+    // so we can only hand out copies of it
+    fn get(&self, range: Range<usize>) -> Cow<'_, str> {
+        match self {
+            Text::Plain(text) => Cow::Borrowed(&text[range]),
+            Text::Compressed(text) => Cow::Owned(text.get(range)),
+        }
+    }
+
+    fn chunks(&self, range: Range<usize>) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match self {
+            Text::Plain(text) => Box::new(std::iter::once(Cow::Borrowed(&text[range]))),
+            Text::Compressed(text) => Box::new(text.chunks(range).map(Cow::Owned)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct TextUsage {
-    text: String,
+    text: Text,
     sarray: SparseRSVec,
+    // with deduplication, maps each text id to the entry of its distinct
+    // value in the text
+    value_ids: Option<CompactVector>,
 }
 
 impl TextUsage {
     pub(crate) fn heap_size(&self) -> usize {
-        self.text.len() + self.sarray.heap_size()
+        self.text.heap_size()
+            + self.sarray.heap_size()
+            + self
+                .value_ids
+                .as_ref()
+                .map(|value_ids| value_ids.size_in_bytes())
+                .unwrap_or(0)
+    }
+
+    pub(crate) fn clear_cache(&self) {
+        if let Text::Compressed(text) = &self.text {
+            text.clear_cache();
+        }
     }
 
     #[allow(dead_code)]
@@ -73,15 +212,69 @@ impl TextUsage {
         }
     }
 
+    // the entry in the text for this text id. Without deduplication every
+    // text has its own entry.
+    pub(crate) fn entry(&self, text_id: TextId) -> TextId {
+        if let Some(value_ids) = &self.value_ids {
+            TextId(value_ids.get_int(text_id.0).expect("Text id should exist"))
+        } else {
+            text_id
+        }
+    }
+
+    // the distinct value for this text id, if we deduplicate
+    pub(crate) fn value_id(&self, text_id: TextId) -> Option<usize> {
+        self.value_ids.as_ref().map(|_| self.entry(text_id).0)
+    }
+
     pub(crate) fn text_range(&self, text_id: TextId) -> Range<usize> {
-        let start = self.text_index(text_id);
-        let end = self.text_index(TextId(text_id.0 + 1));
+        let entry = self.entry(text_id);
+        let start = self.text_index(entry);
+        let end = self.text_index(TextId(entry.0 + 1));
         start..(end - 1)
     }
 
-    pub(crate) fn text_value(&self, text_id: TextId) -> &str {
+    pub(crate) fn text_value(&self, text_id: TextId) -> Cow<'_, str> {
         let range = self.text_range(text_id);
-        &self.text[range]
+        self.text.get(range)
+    }
+
+    // the texts for a range of text ids, in order. This goes through the
+    // underlying text directly, splitting it on terminators.
+    pub(crate) fn texts(
+        &self,
+        text_ids: Range<usize>,
+    ) -> Box<dyn Iterator<Item = (TextId, Cow<'_, str>)> + '_> {
+        if text_ids.is_empty() {
+            return Box::new(std::iter::empty());
+        }
+        if self.value_ids.is_some() {
+            // with deduplication the texts aren't stored in order
+            return Box::new(text_ids.map(|id| (TextId(id), self.text_value(TextId(id)))));
+        }
+        let start = self.text_index(TextId(text_ids.start));
+        let end = self.text_index(TextId(text_ids.end));
+        Box::new(
+            self.text
+                .chunks(start..end)
+                .flat_map(split_texts)
+                .enumerate()
+                .map(move |(i, text)| (TextId(text_ids.start + i), text)),
+        )
+    }
+}
+
+// split a chunk of text on its terminators
+fn split_texts(chunk: Cow<'_, str>) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+    match chunk {
+        Cow::Borrowed(chunk) => Box::new(chunk.split_terminator('\0').map(Cow::Borrowed)),
+        Cow::Owned(chunk) => Box::new(
+            chunk
+                .split_terminator('\0')
+                .map(|text| Cow::Owned(text.to_string()))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        ),
     }
 }
 
@@ -152,4 +345,128 @@ mod tests {
         assert_eq!(usage.text_value(TextId(0)), "hello");
         assert_eq!(usage.text_value(TextId(1)), "world");
     }
+
+    #[test]
+    fn test_compressed_value() {
+        let mut builder = TextBuilder::with_storage(TextStorage::Compressed);
+        builder.text_node("hello");
+        builder.text_node("");
+        builder.text_node("world");
+        let usage = builder.build();
+
+        assert_eq!(usage.text_range(TextId(2)), 7..12);
+        assert_eq!(usage.text_value(TextId(0)), "hello");
+        assert_eq!(usage.text_value(TextId(1)), "");
+        assert_eq!(usage.text_value(TextId(2)), "world");
+    }
+
+    #[test]
+    fn test_compressed_many_blocks() {
+        let mut builder = TextBuilder::with_storage(TextStorage::Compressed);
+        let texts = (0..10_000)
+            .map(|i| format!("text number {} in a longer document", i))
+            .collect::<Vec<_>>();
+        for text in &texts {
+            builder.text_node(text);
+        }
+        let usage = builder.build();
+
+        // access out of order so we hit different blocks
+        for i in (0..texts.len()).rev().step_by(7) {
+            assert_eq!(usage.text_value(TextId(i)), texts[i]);
+        }
+        usage.clear_cache();
+        for (i, text) in texts.iter().enumerate() {
+            assert_eq!(usage.text_value(TextId(i)), text.as_str());
+        }
+    }
+
+    #[test]
+    fn test_compressed_heap_size() {
+        let mut plain_builder = TextBuilder::new();
+        let mut compressed_builder = TextBuilder::with_storage(TextStorage::Compressed);
+        for _ in 0..1000 {
+            plain_builder.text_node("the same sentence, over and over again");
+            compressed_builder.text_node("the same sentence, over and over again");
+        }
+        let plain = plain_builder.build();
+        let compressed = compressed_builder.build();
+        assert!(compressed.heap_size() < plain.heap_size() / 4);
+    }
+
+    #[test]
+    fn test_texts() {
+        for storage in [
+            TextStorage::Plain,
+            TextStorage::Compressed,
+            TextStorage::Deduplicated,
+        ] {
+            let mut builder = TextBuilder::with_storage(storage);
+            builder.text_node("a");
+            builder.text_node("");
+            builder.text_node("b");
+            builder.text_node("a");
+            let usage = builder.build();
+
+            assert_eq!(
+                usage.texts(0..4).collect::<Vec<_>>(),
+                vec![
+                    (TextId(0), Cow::from("a")),
+                    (TextId(1), Cow::from("")),
+                    (TextId(2), Cow::from("b")),
+                    (TextId(3), Cow::from("a"))
+                ]
+            );
+            assert_eq!(
+                usage.texts(1..3).collect::<Vec<_>>(),
+                vec![(TextId(1), Cow::from("")), (TextId(2), Cow::from("b"))]
+            );
+            assert_eq!(usage.texts(2..2).collect::<Vec<_>>(), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_compressed_texts_many_blocks() {
+        let mut builder = TextBuilder::with_storage(TextStorage::Compressed);
+        let texts = (0..10_000)
+            .map(|i| format!("text number {} in a longer document", i))
+            .collect::<Vec<_>>();
+        for text in &texts {
+            builder.text_node(text);
+        }
+        let usage = builder.build();
+        let found = usage.texts(100..9000).map(|(_, s)| s).collect::<Vec<_>>();
+        assert_eq!(found, texts[100..9000].to_vec());
+    }
+
+    #[test]
+    fn test_deduplicated_value() {
+        let mut builder = TextBuilder::with_storage(TextStorage::Deduplicated);
+        builder.text_node("string");
+        builder.text_node("int");
+        builder.text_node("string");
+        builder.text_node("");
+        builder.text_node("int");
+        let usage = builder.build();
+
+        assert_eq!(usage.text_value(TextId(0)), "string");
+        assert_eq!(usage.text_value(TextId(1)), "int");
+        assert_eq!(usage.text_value(TextId(2)), "string");
+        assert_eq!(usage.text_value(TextId(3)), "");
+        assert_eq!(usage.text_value(TextId(4)), "int");
+
+        assert_eq!(usage.value_id(TextId(0)), Some(0));
+        assert_eq!(usage.value_id(TextId(1)), Some(1));
+        assert_eq!(usage.value_id(TextId(2)), Some(0));
+        assert_eq!(usage.value_id(TextId(3)), Some(2));
+        assert_eq!(usage.value_id(TextId(4)), Some(1));
+    }
+
+    #[test]
+    fn test_plain_has_no_value_id() {
+        let mut builder = TextBuilder::new();
+        builder.text_node("string");
+        let usage = builder.build();
+        assert_eq!(usage.value_id(TextId(0)), None);
+    }
 }
@@ -0,0 +1,110 @@
+use vers_vecs::SparseRSVec;
+
+// Where the text nodes of a document start in its plain text: the text of
+// all its text nodes concatenated, which is the string value of the
+// document. Attributes, comments and processing instructions are not part
+// of it. Text nodes are identified by their number in document order, which
+// the structure gives by rank and select on the text node info id.
+#[derive(Debug)]
+pub(crate) struct TextOffsets {
+    // the start of each text node in the plain text plus its number, so that
+    // the positions stay distinct even if a text node is empty
+    starts: SparseRSVec,
+    amount: usize,
+    len: usize,
+}
+
+impl TextOffsets {
+    pub(crate) fn new(lengths: impl Iterator<Item = usize>) -> Self {
+        let mut positions = Vec::new();
+        let mut len = 0;
+        for (i, length) in lengths.enumerate() {
+            positions.push((len + i) as u64);
+            len += length;
+        }
+        let amount = positions.len();
+        Self {
+            starts: SparseRSVec::new(&positions, (len + amount) as u64),
+            amount,
+            len,
+        }
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        self.starts.heap_size()
+    }
+
+    // the offset where text node number i starts. Past the last text node
+    // this is the end of the plain text.
+    pub(crate) fn start(&self, i: usize) -> usize {
+        if i >= self.amount {
+            self.len
+        } else {
+            self.starts.select1(i) as usize - i
+        }
+    }
+
+    // the number of the text node at an offset in the plain text, and the
+    // offset within it. An offset at the boundary of two text nodes is at the
+    // start of the second one, except for the end of the text, which is at
+    // the end of the last text node.
+    pub(crate) fn locate(&self, offset: usize) -> Option<(usize, usize)> {
+        if self.amount == 0 || offset > self.len {
+            return None;
+        }
+        // binary search for the last text node that starts at or before the
+        // offset; the first one starts at 0, so there always is one
+        let mut low = 0;
+        let mut high = self.amount;
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.start(mid) <= offset {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some((low, offset - self.start(low)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let offsets = TextOffsets::new([3, 2, 1].into_iter());
+        assert_eq!(offsets.locate(0), Some((0, 0)));
+        assert_eq!(offsets.locate(2), Some((0, 2)));
+        assert_eq!(offsets.locate(3), Some((1, 0)));
+        assert_eq!(offsets.locate(5), Some((2, 0)));
+        assert_eq!(offsets.locate(6), Some((2, 1)));
+        assert_eq!(offsets.locate(7), None);
+    }
+
+    #[test]
+    fn test_start() {
+        let offsets = TextOffsets::new([3, 2, 1].into_iter());
+        assert_eq!(offsets.start(0), 0);
+        assert_eq!(offsets.start(1), 3);
+        assert_eq!(offsets.start(2), 5);
+        assert_eq!(offsets.start(3), 6);
+    }
+
+    #[test]
+    fn test_empty_text_node() {
+        let offsets = TextOffsets::new([2, 0, 1].into_iter());
+        assert_eq!(offsets.start(1), 2);
+        assert_eq!(offsets.start(2), 2);
+        assert_eq!(offsets.locate(2), Some((2, 0)));
+        assert_eq!(offsets.locate(3), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_no_text_nodes() {
+        let offsets = TextOffsets::new(std::iter::empty());
+        assert_eq!(offsets.locate(0), None);
+        assert_eq!(offsets.start(0), 0);
+    }
+}
@@ -35,6 +35,20 @@ impl<'a> TraverseIter<'a> {
             stack: Vec::new(),
         }
     }
+
+    // after an open item, continue with the close item of that same node
+    // rather than with its children
+    pub(crate) fn skip_children(&mut self) {
+        self.node = None;
+    }
+
+    fn next_sibling(&self, node: Node) -> Option<Node> {
+        if self.stack.is_empty() {
+            None
+        } else {
+            self.doc.next_sibling(node)
+        }
+    }
 }
 
 impl<'a> Iterator for TraverseIter<'a> {
@@ -45,10 +59,12 @@ impl<'a> Iterator for TraverseIter<'a> {
         // When we cannot go further down, we take the next sibling of the
         // current node. When we cannot go further with next sibling, we pop
         // the stack. We know a child is empty if there is no first child.
+        // When the stack is empty we are at the node we started with, and we
+        // stop there rather than continuing with its siblings.
         match self.node {
             None => {
                 if let Some(node) = self.stack.pop() {
-                    self.node = self.doc.next_sibling(node);
+                    self.node = self.next_sibling(node);
                     Some((self.doc.node_type(node), TraverseState::Close, node))
                 } else {
                     None
@@ -60,7 +76,58 @@ impl<'a> Iterator for TraverseIter<'a> {
                     self.node = Some(child);
                     TraverseState::Open
                 } else {
-                    self.node = self.doc.next_sibling(node);
+                    self.node = self.next_sibling(node);
+                    TraverseState::Empty
+                };
+                Some((self.doc.node_type(node), open_close, node))
+            }
+        }
+    }
+}
+
+// Like TraverseIter, but in reverse document order: we go down by last
+// child and continue with the previous sibling. A node with children is
+// closed on the way down and opened on the way back up.
+pub(crate) struct ReverseTraverseIter<'a> {
+    doc: &'a Document,
+    node: Option<Node>,
+    stack: Vec<Node>,
+}
+
+impl<'a> ReverseTraverseIter<'a> {
+    pub(crate) fn new(doc: &'a Document, node: Node) -> Self {
+        Self {
+            doc,
+            node: Some(node),
+            stack: Vec::new(),
+        }
+    }
+
+    fn previous_sibling(&self, node: Node) -> Option<Node> {
+        if self.stack.is_empty() {
+            None
+        } else {
+            self.doc.previous_sibling(node)
+        }
+    }
+}
+
+impl<'a> Iterator for ReverseTraverseIter<'a> {
+    type Item = (&'a NodeType<'a>, TraverseState, Node);
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.node {
+            None => {
+                let node = self.stack.pop()?;
+                self.node = self.previous_sibling(node);
+                Some((self.doc.node_type(node), TraverseState::Open, node))
+            }
+            Some(node) => {
+                let open_close = if let Some(child) = self.doc.last_child(node) {
+                    self.stack.push(node);
+                    self.node = Some(child);
+                    TraverseState::Close
+                } else {
+                    self.node = self.previous_sibling(node);
                     TraverseState::Empty
                 };
                 Some((self.doc.node_type(node), open_close, node))
@@ -334,6 +401,99 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_stops_at_start_node() {
+        let doc = parse_document("<a><b><c/></b><d/></a>").unwrap();
+        let a = doc.document_element();
+        let b = doc.first_child(a).unwrap();
+        let c = doc.first_child(b).unwrap();
+        let d = doc.next_sibling(b).unwrap();
+
+        let traverse = TraverseIter::new(&doc, b)
+            .map(|(_, state, node)| (state, node))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            traverse,
+            vec![
+                (TraverseState::Open, b),
+                (TraverseState::Empty, c),
+                (TraverseState::Close, b),
+            ]
+        );
+        let traverse = TraverseIter::new(&doc, d).collect::<Vec<_>>();
+        assert_eq!(
+            traverse,
+            vec![(
+                &NodeType::Element(NodeName::new("", "d")),
+                TraverseState::Empty,
+                d
+            )]
+        );
+    }
+
+    #[test]
+    fn test_skip_children() {
+        let doc = parse_document("<a><b><c/></b><d><e/></d></a>").unwrap();
+        let a = doc.document_element();
+        let b = doc.first_child(a).unwrap();
+        let d = doc.next_sibling(b).unwrap();
+        let e = doc.first_child(d).unwrap();
+
+        let mut traverse = TraverseIter::new(&doc, a);
+        let mut items = Vec::new();
+        while let Some((_, state, node)) = traverse.next() {
+            if node == b && state == TraverseState::Open {
+                traverse.skip_children();
+            }
+            items.push((state, node));
+        }
+        assert_eq!(
+            items,
+            vec![
+                (TraverseState::Open, a),
+                (TraverseState::Open, b),
+                (TraverseState::Close, b),
+                (TraverseState::Open, d),
+                (TraverseState::Empty, e),
+                (TraverseState::Close, d),
+                (TraverseState::Close, a),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let doc = parse_document("<a><b><c/>text</b><e/></a>").unwrap();
+        let a = doc.document_element();
+        let b = doc.first_child(a).unwrap();
+        let c = doc.first_child(b).unwrap();
+        let text = doc.next_sibling(c).unwrap();
+        let e = doc.next_sibling(b).unwrap();
+
+        let forward = TraverseIter::new(&doc, a)
+            .map(|(_, state, node)| (state, node))
+            .collect::<Vec<_>>();
+        let reverse = ReverseTraverseIter::new(&doc, a)
+            .map(|(_, state, node)| (state, node))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reverse,
+            vec![
+                (TraverseState::Close, a),
+                (TraverseState::Empty, e),
+                (TraverseState::Close, b),
+                (TraverseState::Empty, text),
+                (TraverseState::Empty, c),
+                (TraverseState::Open, b),
+                (TraverseState::Open, a),
+            ]
+        );
+        // the same items as going forward, in reverse
+        let mut forward_reversed = forward;
+        forward_reversed.reverse();
+        assert_eq!(reverse, forward_reversed);
+    }
+
     #[test]
     fn test_attributes() {
         let doc = parse_document(r#"<a b="B" c="C"/>"#).unwrap();
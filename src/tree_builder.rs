@@ -166,9 +166,10 @@ pub(crate) struct TreeBuilder {
     pub(crate) node_info_lookup: NodeInfoLookup,
 
     pub(crate) parentheses: BitVec,
-    // store the opening parens of all text content, i.e. text nodes
-    // and attribute values. We store this separately even though there's
-    // some overlap with the tag table as it's more convenient to calculate text id
+    // store the opening parens of all text content, i.e. text nodes,
+    // attribute values, comments and processing instructions. We store this
+    // separately even though there's some overlap with the tag table as it's
+    // more convenient to calculate text id
     pub(crate) text_opening_parens: BitVec,
     // stores tag ids, but as u64 for convenience of later construction
     usage: Vec<u64>,
@@ -200,7 +201,10 @@ impl TreeBuilder {
         self.parentheses.append(true);
 
         match node_type {
-            NodeType::Attribute { .. } | NodeType::Text => {
+            NodeType::Attribute { .. }
+            | NodeType::Text
+            | NodeType::Comment
+            | NodeType::ProcessingInstruction => {
                 self.text_opening_parens.append(true);
             }
             _ => {
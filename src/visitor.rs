@@ -0,0 +1,57 @@
+use crate::{traverse::TraverseIter, Node, TraverseState, Xoz};
+
+/// What to do after a [`Visitor`] callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VisitControl {
+    /// Continue the traversal as normal.
+    Continue,
+    /// Don't visit the children of the node that was just entered. The node
+    /// is still left. Returned from [`Visitor::leave`] this is the same as
+    /// [`VisitControl::Continue`].
+    SkipChildren,
+    /// Stop the traversal entirely.
+    Stop,
+}
+
+/// A visitor for [`Xoz::visit`].
+///
+/// Both callbacks continue the traversal by default, so you only need to
+/// implement the ones you need.
+pub trait Visitor {
+    /// Called when a node is entered, before its children are visited.
+    fn enter(&mut self, _xoz: &Xoz, _node: Node) -> VisitControl {
+        VisitControl::Continue
+    }
+
+    /// Called when a node is left, after its children are visited.
+    fn leave(&mut self, _xoz: &Xoz, _node: Node) -> VisitControl {
+        VisitControl::Continue
+    }
+}
+
+pub(crate) fn visit(xoz: &Xoz, node: Node, visitor: &mut impl Visitor) {
+    let document = xoz.document(node.document_id);
+    let mut traverse = TraverseIter::new(document, node.document_node);
+    while let Some((_, tag_state, n)) = traverse.next() {
+        let n = document.new_node(n);
+        let control = match tag_state {
+            TraverseState::Open => {
+                let control = visitor.enter(xoz, n);
+                if control == VisitControl::SkipChildren {
+                    // this jumps over the subtree, so the next item is the
+                    // close of this node
+                    traverse.skip_children();
+                }
+                control
+            }
+            TraverseState::Empty => match visitor.enter(xoz, n) {
+                VisitControl::Stop => VisitControl::Stop,
+                _ => visitor.leave(xoz, n),
+            },
+            TraverseState::Close => visitor.leave(xoz, n),
+        };
+        if control == VisitControl::Stop {
+            return;
+        }
+    }
+}
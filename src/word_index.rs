@@ -0,0 +1,286 @@
+use std::borrow::Cow;
+
+use ahash::{HashMap, HashMapExt};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text::TextId;
+
+/// A query against the words in text nodes.
+///
+/// Words are found using Unicode word segmentation and are compared case
+/// insensitively. The text given to construct a query is split into words
+/// the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordQuery {
+    kind: WordQueryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WordQueryKind {
+    Phrase(Vec<String>),
+    Near(String, String, usize),
+}
+
+impl WordQuery {
+    /// Query for a single word.
+    ///
+    /// If the given text contains multiple words, this is the same as a
+    /// phrase query.
+    pub fn term(word: &str) -> Self {
+        Self::phrase(word)
+    }
+
+    /// Query for a sequence of words directly following each other.
+    pub fn phrase(phrase: &str) -> Self {
+        Self {
+            kind: WordQueryKind::Phrase(words(phrase).map(|(_, word)| word).collect()),
+        }
+    }
+
+    /// Query for two words that are at most `distance` words apart, in any
+    /// order.
+    ///
+    /// A distance of 1 means the words are adjacent.
+    pub fn near(first: &str, second: &str, distance: usize) -> Self {
+        Self {
+            kind: WordQueryKind::Near(fold(first), fold(second), distance),
+        }
+    }
+}
+
+fn fold(word: &str) -> String {
+    word.to_lowercase()
+}
+
+// the words in a text along with their byte offset, case folded
+fn words(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.unicode_word_indices()
+        .map(|(offset, word)| (offset, fold(word)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Posting {
+    pub(crate) text_id: TextId,
+    // the byte offset of the word in its text
+    pub(crate) offset: usize,
+    // the position of the word counted over all indexed texts, so that
+    // phrases can continue into the next text, for instance past inline
+    // markup
+    position: usize,
+}
+
+// An inverted index from each word to where it occurs. The postings of a
+// word are in document order. The index doesn't know about blocks; a match
+// is checked to stay within one when it is looked up.
+#[derive(Debug)]
+pub(crate) struct WordIndex {
+    postings: HashMap<String, Vec<Posting>>,
+}
+
+impl WordIndex {
+    pub(crate) fn new<'a>(texts: impl Iterator<Item = (TextId, Cow<'a, str>)>) -> Self {
+        let mut postings: HashMap<String, Vec<Posting>> = HashMap::new();
+        let mut position = 0;
+        for (text_id, text) in texts {
+            for (offset, word) in words(&text) {
+                postings.entry(word).or_default().push(Posting {
+                    text_id,
+                    offset,
+                    position,
+                });
+                position += 1;
+            }
+        }
+        Self { postings }
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        self.postings.capacity() * std::mem::size_of::<(String, Vec<Posting>)>()
+            + self
+                .postings
+                .iter()
+                .map(|(word, postings)| {
+                    word.capacity() + postings.capacity() * std::mem::size_of::<Posting>()
+                })
+                .sum::<usize>()
+    }
+
+    fn postings(&self, word: &str) -> &[Posting] {
+        self.postings.get(word).map(|p| p.as_slice()).unwrap_or(&[])
+    }
+
+    // the matches of the query as the postings of their first and last word,
+    // in document order. A match can only go on from one word to the next if
+    // same_block says they are in the same block.
+    pub(crate) fn matches(
+        &self,
+        query: &WordQuery,
+        same_block: impl Fn(&Posting, &Posting) -> bool,
+    ) -> Vec<(Posting, Posting)> {
+        match &query.kind {
+            WordQueryKind::Phrase(words) => self.phrase_matches(words, same_block),
+            WordQueryKind::Near(first, second, distance) => {
+                self.near_matches(first, second, *distance, same_block)
+            }
+        }
+    }
+
+    fn phrase_matches(
+        &self,
+        words: &[String],
+        same_block: impl Fn(&Posting, &Posting) -> bool,
+    ) -> Vec<(Posting, Posting)> {
+        let Some((first_word, rest)) = words.split_first() else {
+            return Vec::new();
+        };
+        let rest = rest
+            .iter()
+            .map(|word| self.postings(word))
+            .collect::<Vec<_>>();
+        self.postings(first_word)
+            .iter()
+            .filter_map(|first| {
+                let mut last = *first;
+                for (i, postings) in rest.iter().enumerate() {
+                    last = *find_position(postings, first.position + i + 1)
+                        .filter(|next| same_block(&last, next))?;
+                }
+                Some((*first, last))
+            })
+            .collect()
+    }
+
+    fn near_matches(
+        &self,
+        first: &str,
+        second: &str,
+        distance: usize,
+        same_block: impl Fn(&Posting, &Posting) -> bool,
+    ) -> Vec<(Posting, Posting)> {
+        let second_postings = self.postings(second);
+        let mut matches = Vec::new();
+        for a in self.postings(first) {
+            let start = second_postings.partition_point(|b| b.position + distance < a.position);
+            for b in second_postings[start..]
+                .iter()
+                .take_while(|b| b.position <= a.position + distance)
+            {
+                if b.position == a.position || !same_block(a, b) {
+                    continue;
+                }
+                if a.position < b.position {
+                    matches.push((*a, *b));
+                } else {
+                    matches.push((*b, *a));
+                }
+            }
+        }
+        matches.sort_by_key(|(first, last)| (first.position, last.position));
+        matches.dedup();
+        matches
+    }
+}
+
+fn find_position(postings: &[Posting], position: usize) -> Option<&Posting> {
+    postings
+        .binary_search_by_key(&position, |p| p.position)
+        .ok()
+        .map(|i| &postings[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(texts: &[&str]) -> WordIndex {
+        WordIndex::new(
+            texts
+                .iter()
+                .enumerate()
+                .map(|(i, text)| (TextId::new(i), Cow::Borrowed(*text))),
+        )
+    }
+
+    fn found(index: &WordIndex, query: &WordQuery) -> Vec<(usize, usize)> {
+        found_in_blocks(index, query, &[])
+    }
+
+    // blocks gives the block of each text, by text id; texts past its end
+    // are in block 0
+    fn found_in_blocks(
+        index: &WordIndex,
+        query: &WordQuery,
+        blocks: &[usize],
+    ) -> Vec<(usize, usize)> {
+        let block = |posting: &Posting| blocks.get(posting.text_id.id()).copied().unwrap_or(0);
+        index
+            .matches(query, |a, b| block(a) == block(b))
+            .iter()
+            .map(|(first, last)| (first.text_id.id(), last.text_id.id()))
+            .collect()
+    }
+
+    #[test]
+    fn test_term_case_folded() {
+        let index = index(&["Hello world", "HELLO there", "say hello."]);
+        assert_eq!(
+            found(&index, &WordQuery::term("hello")),
+            vec![(0, 0), (1, 1), (2, 2)]
+        );
+        assert_eq!(found(&index, &WordQuery::term("missing")), vec![]);
+    }
+
+    #[test]
+    fn test_term_offset() {
+        let index = index(&["über straße, Straße"]);
+        let matches = index.matches(&WordQuery::term("STRAßE"), |_, _| true);
+        let offsets = matches.iter().map(|(p, _)| p.offset).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![6, 15]);
+    }
+
+    #[test]
+    fn test_phrase() {
+        let index = index(&["the quick brown fox", "a quick fox", "quick"]);
+        assert_eq!(
+            found(&index, &WordQuery::phrase("Quick brown")),
+            vec![(0, 0)]
+        );
+        assert_eq!(found(&index, &WordQuery::phrase("quick fox")), vec![(1, 1)]);
+        assert_eq!(found(&index, &WordQuery::phrase("")), vec![]);
+    }
+
+    #[test]
+    fn test_phrase_over_texts() {
+        let index = index(&["the quick ", "brown", " fox"]);
+        assert_eq!(
+            found(&index, &WordQuery::phrase("quick brown fox")),
+            vec![(0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_not_over_blocks() {
+        let index = index(&["the quick", "brown fox", " jumps"]);
+        let blocks = [0, 1, 1];
+        let found = |query| found_in_blocks(&index, &query, &blocks);
+        assert_eq!(found(WordQuery::phrase("quick brown")), vec![]);
+        assert_eq!(found(WordQuery::phrase("fox jumps")), vec![(1, 2)]);
+        assert_eq!(found(WordQuery::near("the", "brown", 2)), vec![]);
+        assert_eq!(found(WordQuery::near("brown", "jumps", 2)), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn test_near() {
+        let index = index(&["alpha beta gamma delta", "delta x y z alpha"]);
+        assert_eq!(found(&index, &WordQuery::near("alpha", "gamma", 1)), vec![]);
+        assert_eq!(
+            found(&index, &WordQuery::near("alpha", "gamma", 2)),
+            vec![(0, 0)]
+        );
+        // in either order, and over texts
+        assert_eq!(
+            found(&index, &WordQuery::near("delta", "alpha", 4)),
+            vec![(0, 0), (0, 1), (1, 1)]
+        );
+    }
+}
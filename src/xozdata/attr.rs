@@ -1,4 +1,8 @@
+use std::borrow::Cow;
+
 use crate::NodeName;
+#[cfg(doc)]
+use crate::TextStorage;
 
 use super::core::{Node, Xoz};
 
@@ -60,10 +64,34 @@ impl Xoz {
         document.attribute_value(node.document_node, name)
     }
 
+    /// Get the value of the attribute with the given name, with any text
+    /// storage.
+    ///
+    /// This is like [`Xoz::attribute_value`], but it also works with
+    /// [`TextStorage::Compressed`], in which case it gives a copy of the
+    /// value.
+    ///
+    /// ```rust
+    /// use xoz::{TextStorage, Xoz};
+    /// let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    /// let root = xoz.parse_str(r#"<p a="1" b="2"/>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    /// assert_eq!(xoz.attribute_text(p, "a").as_deref(), Some("1"));
+    /// assert_eq!(xoz.attribute_text(p, "c"), None);
+    /// ```
+    pub fn attribute_text<'a>(
+        &self,
+        node: Node,
+        name: impl Into<NodeName<'a>>,
+    ) -> Option<Cow<'_, str>> {
+        let document = self.document(node.document_id);
+        document.attribute_text(node.document_node, name)
+    }
+
     /// Get an iterator over the name and value of all attributes of this node.
     ///
     /// If this is not an element node, it returns an empty iterator.
-    pub fn attribute_entries(&self, node: Node) -> impl Iterator<Item = (&NodeName, &str)> {
+    pub fn attribute_entries(&self, node: Node) -> impl Iterator<Item = (&NodeName<'_>, &str)> {
         let document = self.document(node.document_id);
         document.attribute_entries(node.document_node)
     }
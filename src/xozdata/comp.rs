@@ -108,10 +108,10 @@ impl Xoz {
                 a_name == b_name && self.advanced_compare_attributes(a, b, text_compare)
             }
             (NodeType::Text, NodeType::Text) => {
-                text_compare(self.node_str(a).unwrap(), self.node_str(b).unwrap())
+                text_compare(&self.node_text(a).unwrap(), &self.node_text(b).unwrap())
             }
             (NodeType::Comment, NodeType::Comment) => {
-                self.node_str(a).unwrap() == self.node_str(b).unwrap()
+                self.node_text(a).unwrap() == self.node_text(b).unwrap()
             }
             (NodeType::ProcessingInstruction, NodeType::ProcessingInstruction) => {
                 let a_pi = self.processing_instruction(a).unwrap();
@@ -125,7 +125,7 @@ impl Xoz {
             }
             (NodeType::Attribute(a_name), NodeType::Attribute(b_name)) => {
                 a_name == b_name
-                    && text_compare(self.node_str(a).unwrap(), self.node_str(b).unwrap())
+                    && text_compare(&self.node_text(a).unwrap(), &self.node_text(b).unwrap())
             }
             (NodeType::Namespace(a_ns), NodeType::Namespace(b_ns)) => a_ns == b_ns,
             _ => false,
@@ -146,10 +146,11 @@ impl Xoz {
                 if a_size != b_size {
                     return false;
                 }
-                for (key, value_a) in self.attribute_entries(a) {
-                    let value_b = self.attribute_value(b, key.clone());
+                let document = self.document(a.document_id);
+                for (key, value_a) in document.attribute_texts(a.document_node) {
+                    let value_b = self.attribute_text(b, key.clone());
                     if let Some(value_b) = value_b {
-                        if !text_compare(value_a, value_b) {
+                        if !text_compare(&value_a, &value_b) {
                             return false;
                         }
                     } else {
@@ -1,16 +1,69 @@
 use crate::document::{Document, DocumentId, Node as DocumentNode};
 use crate::error::quickxml::Result;
 use crate::parser::parse_document_with_id;
+use crate::TextStorage;
 
 /// A node in the Xoz structure.
 ///
 /// A node can be in any document load into the Xoz structure.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Nodes are ordered in document order: nodes in documents parsed earlier
+/// come first, and within a document nodes are in preorder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Node {
     pub(crate) document_id: DocumentId,
     pub(crate) document_node: DocumentNode,
 }
 
+/// A stable identifier of a node.
+///
+/// Unlike [`Node`], this can be stored outside of the program, for instance
+/// in a search index on disk, and turned back into a node with
+/// [`Xoz::node_from_id`] later. This works as long as the same documents are
+/// parsed into the Xoz structure in the same order.
+///
+/// Obtain it with [`Xoz::node_id`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId {
+    document: u64,
+    position: u64,
+}
+
+impl NodeId {
+    /// Construct a node id from its document index and tree position.
+    pub fn new(document: u64, position: u64) -> Self {
+        Self { document, position }
+    }
+
+    /// The index of the document in the Xoz structure, in the order the
+    /// documents were parsed.
+    pub fn document(&self) -> u64 {
+        self.document
+    }
+
+    /// The position of the node in the tree structure of its document.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The node id as bytes, to store it.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.document.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.position.to_le_bytes());
+        bytes
+    }
+
+    /// Construct a node id from bytes produced by [`NodeId::to_bytes`].
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        let (document, position) = bytes.split_at(8);
+        Self {
+            document: u64::from_le_bytes(document.try_into().unwrap()),
+            position: u64::from_le_bytes(position.try_into().unwrap()),
+        }
+    }
+}
+
 /// The Xoz structure holds all XML documents and is used for accessing them.
 ///
 /// All operations on nodes are done through this structure. Combining nodes
@@ -33,8 +86,12 @@ pub struct Node {
 /// * [Attribute](#attribute)
 /// * [Iteration](#iteration)
 /// * [Comparison](#comparison)
+/// * [Search](#search)
+/// * [Path](#path)
+/// * [Node reference](#node-reference)
 pub struct Xoz {
     documents: Vec<Document>,
+    text_storage: TextStorage,
 }
 
 impl Document {
@@ -57,16 +114,46 @@ impl Default for Xoz {
 impl Xoz {
     /// Create a new empty Xoz structure.
     pub fn new() -> Self {
+        Self::with_text_storage(TextStorage::Plain)
+    }
+
+    /// Create a new empty Xoz structure that stores the text of the documents
+    /// parsed into it as described by [`TextStorage`].
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, TextStorage};
+    /// let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    /// let root = xoz.parse_str("<p>Example</p>").unwrap();
+    /// let p = xoz.document_element(root);
+    /// let text = xoz.first_child(p).unwrap();
+    /// assert_eq!(xoz.node_text(text).as_deref(), Some("Example"));
+    /// ```
+    pub fn with_text_storage(text_storage: TextStorage) -> Self {
         Xoz {
             documents: Vec::new(),
+            text_storage,
         }
     }
 
     /// Heap size used by the Xoz structure.
+    ///
+    /// With [`TextStorage::Compressed`] this includes the text blocks
+    /// that are currently decompressed. Indexes that are built on first use,
+    /// such as the word index, are included once they've been built.
     pub fn heap_size(&self) -> usize {
         self.documents.iter().map(|d| d.heap_size()).sum()
     }
 
+    /// Drop all decompressed text blocks.
+    ///
+    /// This only has an effect with [`TextStorage::Compressed`]. Blocks are
+    /// decompressed again when their text is accessed.
+    pub fn clear_text_cache(&self) {
+        for document in &self.documents {
+            document.text_usage.clear_cache();
+        }
+    }
+
     pub(crate) fn new_document_id(&self) -> DocumentId {
         DocumentId::new(self.documents.len())
     }
@@ -75,6 +162,10 @@ impl Xoz {
         &self.documents[id.index()]
     }
 
+    pub(crate) fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
     pub(crate) fn wrap(
         &self,
         node: Node,
@@ -95,7 +186,7 @@ impl Xoz {
 
     /// Parse a string slice into a document and return the root node.
     pub fn parse_str(&mut self, xml: &str) -> Result<Node> {
-        let document = parse_document_with_id(self.new_document_id(), xml)?;
+        let document = parse_document_with_id(self.new_document_id(), xml, self.text_storage)?;
         let root = document.root();
         let root = document.new_node(root);
         self.documents.push(document);
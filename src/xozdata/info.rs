@@ -1,6 +1,6 @@
 use crate::{NodeName, NodeType};
 
-use super::core::{Node, Xoz};
+use super::core::{Node, NodeId, Xoz};
 
 /// ## Information
 ///
@@ -17,6 +17,80 @@ impl Xoz {
         document.preorder(node.document_node)
     }
 
+    /// Postorder number of node
+    ///
+    /// This is the position of the node when nodes are ordered by where they
+    /// end, so descendants come before their ancestors. Like with
+    /// [`Xoz::preorder`], there may be gaps.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b/><c/></a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    /// let c = xoz.next_sibling(b).unwrap();
+    /// assert!(xoz.postorder(b) < xoz.postorder(c));
+    /// assert!(xoz.postorder(c) < xoz.postorder(a));
+    /// assert!(xoz.postorder(a) < xoz.postorder(root));
+    /// ```
+    pub fn postorder(&self, node: Node) -> usize {
+        let document = self.document(node.document_id);
+        document.postorder(node.document_node)
+    }
+
+    /// Get the node with a preorder number in the document of `root`.
+    ///
+    /// This is the inverse of [`Xoz::preorder`]. Any node in the document can
+    /// be given as `root`. Returns [`None`] if there is no node with this
+    /// preorder number, which is also the case for the gaps in the preorder.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b/></a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    /// assert_eq!(xoz.node_at_preorder(root, xoz.preorder(b)), Some(b));
+    /// assert_eq!(xoz.node_at_preorder(root, 100), None);
+    /// ```
+    pub fn node_at_preorder(&self, root: Node, preorder: usize) -> Option<Node> {
+        let document = self.document(root.document_id);
+        document
+            .node_at_preorder(preorder)
+            .map(|n| document.new_node(n))
+    }
+
+    /// Get a stable [`NodeId`] for a node.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeId};
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b/></a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    ///
+    /// let bytes = xoz.node_id(b).to_bytes();
+    /// assert_eq!(xoz.node_from_id(NodeId::from_bytes(bytes)), Some(b));
+    /// ```
+    pub fn node_id(&self, node: Node) -> NodeId {
+        NodeId::new(
+            node.document_id.index() as u64,
+            node.document_node.get() as u64,
+        )
+    }
+
+    /// Get the node for a [`NodeId`].
+    ///
+    /// Returns [`None`] if the id doesn't identify a node in this Xoz
+    /// structure.
+    pub fn node_from_id(&self, node_id: NodeId) -> Option<Node> {
+        let document = self.documents().get(node_id.document() as usize)?;
+        document
+            .visible_node(node_id.position() as usize)
+            .map(|n| document.new_node(n))
+    }
+
     /// Sort key for node.
     ///
     /// This can be used to sort nodes in a stable way: nodes in the
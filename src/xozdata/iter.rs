@@ -1,4 +1,4 @@
-use crate::NodeType;
+use crate::{visitor, NodeType, Visitor};
 
 use super::core::{Node, Xoz};
 
@@ -167,8 +167,12 @@ impl Xoz {
     /// let c = xoz.first_child(b).unwrap();
     /// let descendants = xoz.descendants(a).collect::<Vec<_>>();
     /// assert_eq!(descendants, vec![b, c]);
+    ///
+    /// // it can also go in reverse document order
+    /// let descendants = xoz.descendants(a).rev().collect::<Vec<_>>();
+    /// assert_eq!(descendants, vec![c, b]);
     /// ```
-    pub fn descendants(&self, node: Node) -> impl Iterator<Item = Node> + '_ {
+    pub fn descendants(&self, node: Node) -> impl DoubleEndedIterator<Item = Node> + '_ {
         let document = self.document(node.document_id);
         document
             .descendants(node.document_node)
@@ -321,6 +325,96 @@ impl Xoz {
             .map(move |n| document.new_node(n))
     }
 
+    /// Iterate over descendants that have any of the given node types, using
+    /// jumping.
+    ///
+    /// The descendants are given in document order. This is like the XPath
+    /// step `descendant::(a|b)`.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<doc><a/><c><b/></c><a/></doc>").unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let a1 = xoz.first_child(doc_el).unwrap();
+    /// let c = xoz.next_sibling(a1).unwrap();
+    /// let b = xoz.first_child(c).unwrap();
+    /// let a2 = xoz.next_sibling(c).unwrap();
+    ///
+    /// let found = xoz
+    ///     .typed_descendants_any(root, &[NodeType::element("a"), NodeType::element("b")])
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(found, vec![a1, b, a2]);
+    /// ```
+    pub fn typed_descendants_any(
+        &self,
+        node: Node,
+        node_types: &[NodeType],
+    ) -> impl Iterator<Item = Node> + '_ {
+        let document = self.document(node.document_id);
+        document
+            .typed_descendants_any(node.document_node, node_types)
+            .map(move |n| document.new_node(n))
+    }
+
+    /// Iterate over descendant elements in a namespace, using jumping.
+    ///
+    /// This is like the XPath step `descendant::ns:*`. Use an empty string
+    /// for elements that aren't in a namespace.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<doc xmlns:x="http://example.com/x"><x:a/><b/><x:c/></doc>"#).unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let a = xoz.first_child(doc_el).unwrap();
+    /// let b = xoz.next_sibling(a).unwrap();
+    /// let c = xoz.next_sibling(b).unwrap();
+    ///
+    /// let found = xoz.typed_descendants_namespace(root, "http://example.com/x").collect::<Vec<_>>();
+    /// assert_eq!(found, vec![a, c]);
+    /// ```
+    pub fn typed_descendants_namespace<'a>(
+        &'a self,
+        node: Node,
+        namespace: &str,
+    ) -> impl Iterator<Item = Node> + 'a {
+        let document = self.document(node.document_id);
+        document
+            .typed_descendants_namespace(node.document_node, namespace)
+            .map(move |n| document.new_node(n))
+    }
+
+    /// Iterate over descendant elements with a local name in any namespace,
+    /// using jumping.
+    ///
+    /// This is like the XPath step `descendant::*:name`.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<doc xmlns:x="http://example.com/x"><x:a/><b/><a/></doc>"#).unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let xa = xoz.first_child(doc_el).unwrap();
+    /// let a = xoz.last_child(doc_el).unwrap();
+    ///
+    /// let found = xoz.typed_descendants_local_name(root, "a").collect::<Vec<_>>();
+    /// assert_eq!(found, vec![xa, a]);
+    /// ```
+    pub fn typed_descendants_local_name<'a>(
+        &'a self,
+        node: Node,
+        local_name: &str,
+    ) -> impl Iterator<Item = Node> + 'a {
+        let document = self.document(node.document_id);
+        document
+            .typed_descendants_local_name(node.document_node, local_name)
+            .map(move |n| document.new_node(n))
+    }
+
     /// Iterate over following nodes of a certain node type, using jumping.
     pub fn typed_following(
         &self,
@@ -333,6 +427,63 @@ impl Xoz {
             .map(move |n| document.new_node(n))
     }
 
+    /// Iterate over the children of a certain node type, using jumping.
+    ///
+    /// This jumps from one matching child to the next, so children of
+    /// other types are never visited, and neither are the descendants of
+    /// children.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<table><row/><note><row/></note>text<row/></table>").unwrap();
+    /// let table = xoz.document_element(root);
+    /// let row1 = xoz.first_child(table).unwrap();
+    /// let row3 = xoz.last_child(table).unwrap();
+    ///
+    /// let rows = xoz.typed_children(table, NodeType::element("row")).collect::<Vec<_>>();
+    /// assert_eq!(rows, vec![row1, row3]);
+    /// ```
+    pub fn typed_children(
+        &self,
+        node: Node,
+        node_type: NodeType,
+    ) -> impl Iterator<Item = Node> + '_ {
+        let document = self.document(node.document_id);
+        document
+            .typed_children(node.document_node, node_type)
+            .map(move |n| document.new_node(n))
+    }
+
+    /// Iterate over the following siblings of a certain node type, using
+    /// jumping.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<table><row/><note/><row/><row/></table>").unwrap();
+    /// let table = xoz.document_element(root);
+    /// let row1 = xoz.first_child(table).unwrap();
+    /// let note = xoz.next_sibling(row1).unwrap();
+    /// let row2 = xoz.next_sibling(note).unwrap();
+    /// let row3 = xoz.next_sibling(row2).unwrap();
+    ///
+    /// let rows = xoz.typed_following_siblings(row1, NodeType::element("row")).collect::<Vec<_>>();
+    /// assert_eq!(rows, vec![row2, row3]);
+    /// ```
+    pub fn typed_following_siblings(
+        &self,
+        node: Node,
+        node_type: NodeType,
+    ) -> impl Iterator<Item = Node> + '_ {
+        let document = self.document(node.document_id);
+        document
+            .typed_following_siblings(node.document_node, node_type)
+            .map(move |n| document.new_node(n))
+    }
+
     /// Iterate over the nodes in the tree.
     ///
     /// This goes in document order. Attributes and namespace nodes are not included.
@@ -348,4 +499,106 @@ impl Xoz {
             .traverse(node.document_node)
             .map(move |(node_type, tag_state, n)| (node_type, tag_state, document.new_node(n)))
     }
+
+    /// Iterate over the nodes in the tree in reverse document order.
+    ///
+    /// This gives the same items as [`Xoz::traverse`] but in reverse order,
+    /// so an element with children is closed before its children and opened
+    /// after them.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, TraverseState};
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b/>text</a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    /// let text = xoz.next_sibling(b).unwrap();
+    ///
+    /// let items = xoz
+    ///     .reverse_traverse(a)
+    ///     .map(|(_, state, node)| (state, node))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     items,
+    ///     vec![
+    ///         (TraverseState::Close, a),
+    ///         (TraverseState::Empty, text),
+    ///         (TraverseState::Empty, b),
+    ///         (TraverseState::Open, a),
+    ///     ]
+    /// );
+    /// ```
+    pub fn reverse_traverse(
+        &self,
+        node: Node,
+    ) -> impl Iterator<Item = (&NodeType<'_>, crate::TraverseState, Node)> + '_ {
+        let document = self.document(node.document_id);
+        document
+            .reverse_traverse(node.document_node)
+            .map(move |(node_type, tag_state, n)| (node_type, tag_state, document.new_node(n)))
+    }
+
+    /// Iterate over this node and its descendants level by level
+    /// (breadth-first).
+    ///
+    /// First this node, then its children, then their children, and so on.
+    /// Within a level nodes are in document order. Namespace and attribute
+    /// nodes aren't included.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b><d/></b><c/></a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    /// let c = xoz.next_sibling(b).unwrap();
+    /// let d = xoz.first_child(b).unwrap();
+    ///
+    /// let nodes = xoz.level_order(a).collect::<Vec<_>>();
+    /// assert_eq!(nodes, vec![a, b, c, d]);
+    /// ```
+    pub fn level_order(&self, node: Node) -> impl Iterator<Item = Node> + '_ {
+        let document = self.document(node.document_id);
+        document
+            .level_order(node.document_node)
+            .map(move |n| document.new_node(n))
+    }
+
+    /// Visit the nodes in the tree with a [`Visitor`].
+    ///
+    /// This goes in document order like [`Xoz::traverse`], calling
+    /// [`Visitor::enter`] when a node is entered and [`Visitor::leave`] when
+    /// it is left. Nodes without children are entered and left right away.
+    /// The visitor can skip the children of a node, jumping directly to its
+    /// next sibling, or stop the traversal altogether.
+    ///
+    /// ```rust
+    /// use xoz::{Node, NodeType, VisitControl, Visitor, Xoz};
+    ///
+    /// struct ContentText(Vec<String>);
+    ///
+    /// impl Visitor for ContentText {
+    ///     fn enter(&mut self, xoz: &Xoz, node: Node) -> VisitControl {
+    ///         if xoz.node_type(node) == &NodeType::element("metadata") {
+    ///             return VisitControl::SkipChildren;
+    ///         }
+    ///         if let Some(text) = xoz.text_str(node) {
+    ///             self.0.push(text.to_string());
+    ///         }
+    ///         VisitControl::Continue
+    ///     }
+    /// }
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz
+    ///     .parse_str("<doc><metadata><a>skip</a></metadata><p>one</p><p>two</p></doc>")
+    ///     .unwrap();
+    ///
+    /// let mut visitor = ContentText(Vec::new());
+    /// xoz.visit(root, &mut visitor);
+    /// assert_eq!(visitor.0, vec!["one", "two"]);
+    /// ```
+    pub fn visit(&self, node: Node, visitor: &mut impl Visitor) {
+        visitor::visit(self, node, visitor)
+    }
 }
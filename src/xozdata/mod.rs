@@ -6,7 +6,12 @@ mod core;
 mod info;
 mod iter;
 mod nav;
+mod node_ref;
 mod ns;
+mod path;
+mod search;
 mod str;
 
-pub use core::{Node, Xoz};
+pub use core::{Node, NodeId, Xoz};
+pub use node_ref::NodeRef;
+pub use search::Snippet;
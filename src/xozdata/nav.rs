@@ -124,6 +124,79 @@ impl Xoz {
         document.is_ancestor_or_self(ancestor.document_node, descendant.document_node)
     }
 
+    /// The depth of a node in the tree.
+    ///
+    /// The root node has depth 0, the document element depth 1, and so on.
+    /// Attribute and namespace nodes are one level deeper than their element.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<p a="A"><em>Text</em></p>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    /// let a = xoz.attribute_node(p, "a").unwrap();
+    /// let em = xoz.first_child(p).unwrap();
+    /// let text = xoz.first_child(em).unwrap();
+    ///
+    /// assert_eq!(xoz.depth(root), 0);
+    /// assert_eq!(xoz.depth(p), 1);
+    /// assert_eq!(xoz.depth(a), 2);
+    /// assert_eq!(xoz.depth(text), 3);
+    /// ```
+    pub fn depth(&self, node: Node) -> usize {
+        let document = self.document(node.document_id);
+        document.depth(node.document_node)
+    }
+
+    /// The ancestor a number of levels up from a node.
+    ///
+    /// With 0 levels this is the node itself, with 1 level its parent, and so
+    /// on. This doesn't walk up the tree, so it's efficient even for deep
+    /// documents. Returns [`None`] if there are not this many levels above the
+    /// node.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b><c/></b></a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    /// let c = xoz.first_child(b).unwrap();
+    ///
+    /// assert_eq!(xoz.level_ancestor(c, 0), Some(c));
+    /// assert_eq!(xoz.level_ancestor(c, 2), Some(a));
+    /// assert_eq!(xoz.level_ancestor(c, 3), Some(root));
+    /// assert_eq!(xoz.level_ancestor(c, 4), None);
+    /// ```
+    pub fn level_ancestor(&self, node: Node, levels: usize) -> Option<Node> {
+        self.wrap_option(node, |doc, n| doc.level_ancestor(n, levels))
+    }
+
+    /// The lowest common ancestor of two nodes.
+    ///
+    /// This is the deepest node that is an ancestor-or-self of both nodes.
+    /// Returns [`None`] if the nodes are in different documents.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b><c/></b><d/></a>").unwrap();
+    /// let a = xoz.document_element(root);
+    /// let b = xoz.first_child(a).unwrap();
+    /// let c = xoz.first_child(b).unwrap();
+    /// let d = xoz.next_sibling(b).unwrap();
+    ///
+    /// assert_eq!(xoz.lowest_common_ancestor(c, d), Some(a));
+    /// assert_eq!(xoz.lowest_common_ancestor(c, b), Some(b));
+    /// ```
+    pub fn lowest_common_ancestor(&self, a: Node, b: Node) -> Option<Node> {
+        if a.document_id != b.document_id {
+            return None;
+        }
+        let document = self.document(a.document_id);
+        Some(document.new_node(document.lowest_common_ancestor(a.document_node, b.document_node)))
+    }
+
     /// Obtain top node, given node anywhere in a tree
     ///
     /// In an XML document this is the document element.
@@ -182,6 +255,15 @@ impl Xoz {
     ///
     /// Namespace and attribute nodes aren't considered children.
     ///
+    /// This doesn't go through the siblings of the node, so it's efficient
+    /// even if there are many.
+    ///
+    /// The first time you use [`Xoz::child_index`], [`Xoz::child_count`]
+    /// or [`Xoz::nth_child`] on a document, it builds a table with the
+    /// position of each node among its siblings, and keeps it for later
+    /// calls. This takes a few bytes for every node of the document, and
+    /// is included in [`Xoz::heap_size`].
+    ///
     /// ```rust
     /// use xoz::Xoz;
     /// let mut xoz = Xoz::new();
@@ -203,6 +285,49 @@ impl Xoz {
         document.child_index(parent.document_node, node.document_node)
     }
 
+    /// The amount of children of a node.
+    ///
+    /// Namespace and attribute nodes aren't considered children.
+    ///
+    /// This builds the same table as [`Xoz::child_index`] on first use.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<p a="A"><a/>text<b><c/></b></p>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    /// assert_eq!(xoz.child_count(p), 3);
+    /// assert_eq!(xoz.child_count(root), 1);
+    /// ```
+    pub fn child_count(&self, node: Node) -> usize {
+        let document = self.document(node.document_id);
+        document.child_count(node.document_node)
+    }
+
+    /// Get the child with index `n`.
+    ///
+    /// Returns [`None`] if there are not that many children. This doesn't
+    /// go through the children before it, so you can use it to access large
+    /// lists of children at random. It builds the same table as
+    /// [`Xoz::child_index`] on first use.
+    ///
+    /// Namespace and attribute nodes aren't considered children.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<p a="A"><a/><b/></p>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    /// let a = xoz.first_child(p).unwrap();
+    /// let b = xoz.next_sibling(a).unwrap();
+    /// assert_eq!(xoz.nth_child(p, 0), Some(a));
+    /// assert_eq!(xoz.nth_child(p, 1), Some(b));
+    /// assert_eq!(xoz.nth_child(p, 2), None);
+    /// ```
+    pub fn nth_child(&self, node: Node, n: usize) -> Option<Node> {
+        self.wrap_option(node, |doc, node| doc.nth_child(node, n))
+    }
+
     /// Jump to descendant of node type
     ///
     /// Look for the first descendant of node in document order that has
@@ -240,4 +365,77 @@ impl Xoz {
             .typed_foll(node.document_node, node_type)
             .map(|n| document.new_node(n))
     }
+
+    /// Jump to preceding node of node type.
+    ///
+    /// Look for the nearest node before node in document order that has
+    /// [`NodeType`] and that isn't an ancestor of node, as in the XPath
+    /// `preceding` axis.
+    ///
+    /// This jumps directly to this node without having to traverse the tree.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<a><b/><a><b/><c/></a></a>").unwrap();
+    /// let a1 = xoz.document_element(root);
+    /// let b1 = xoz.first_child(a1).unwrap();
+    /// let a2 = xoz.next_sibling(b1).unwrap();
+    /// let b2 = xoz.first_child(a2).unwrap();
+    /// let c = xoz.next_sibling(b2).unwrap();
+    ///
+    /// assert_eq!(xoz.typed_preceding(c, NodeType::element("b")), Some(b2));
+    /// // ancestors are not preceding
+    /// assert_eq!(xoz.typed_preceding(c, NodeType::element("a")), None);
+    /// ```
+    pub fn typed_preceding(&self, node: Node, node_type: NodeType) -> Option<Node> {
+        self.wrap_option(node, |doc, n| doc.typed_preceding(n, node_type.clone()))
+    }
+
+    /// Jump to preceding sibling of node type.
+    ///
+    /// Look for the nearest previous sibling of node that has [`NodeType`].
+    ///
+    /// This jumps directly to this node without having to traverse the tree.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<p><a/><b><a/></b><c/></p>").unwrap();
+    /// let p = xoz.document_element(root);
+    /// let a = xoz.first_child(p).unwrap();
+    /// let c = xoz.last_child(p).unwrap();
+    ///
+    /// assert_eq!(xoz.typed_preceding_sibling(c, NodeType::element("a")), Some(a));
+    /// ```
+    pub fn typed_preceding_sibling(&self, node: Node, node_type: NodeType) -> Option<Node> {
+        self.wrap_option(node, |doc, n| {
+            doc.typed_preceding_sibling(n, node_type.clone())
+        })
+    }
+
+    /// Jump to ancestor of node type.
+    ///
+    /// Look for the nearest ancestor of node that has [`NodeType`]. The node
+    /// itself is not considered.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<section><section><p>Text</p></section></section>").unwrap();
+    /// let section1 = xoz.document_element(root);
+    /// let section2 = xoz.first_child(section1).unwrap();
+    /// let p = xoz.first_child(section2).unwrap();
+    /// let text = xoz.first_child(p).unwrap();
+    ///
+    /// assert_eq!(xoz.typed_ancestor(text, NodeType::element("section")), Some(section2));
+    /// assert_eq!(xoz.typed_ancestor(section2, NodeType::element("section")), Some(section1));
+    /// assert_eq!(xoz.typed_ancestor(section1, NodeType::element("section")), None);
+    /// ```
+    pub fn typed_ancestor(&self, node: Node, node_type: NodeType) -> Option<Node> {
+        self.wrap_option(node, |doc, n| doc.typed_ancestor(n, node_type.clone()))
+    }
 }
@@ -0,0 +1,336 @@
+use std::{borrow::Cow, fmt};
+
+use crate::{NodeName, NodeType};
+
+use super::core::{Node, NodeId, Xoz};
+
+/// A node together with the Xoz structure it is in.
+///
+/// This offers the most common methods of [`Xoz`] as methods on the node
+/// itself, so that navigation can be chained. Methods that give back nodes
+/// give back node references too. Obtain one with [`Xoz::node_ref`].
+///
+/// Its [`Display`](fmt::Display) implementation serializes the node to XML,
+/// and its [`Debug`](fmt::Debug) implementation shows its name and its
+/// [path](Xoz::path).
+///
+/// ```rust
+/// use xoz::Xoz;
+/// let mut xoz = Xoz::new();
+/// let root = xoz.parse_str(r#"<doc><p id="a">Hello</p><p>world</p></doc>"#).unwrap();
+/// let root = xoz.node_ref(root);
+///
+/// let p = root.document_element().first_child().unwrap();
+/// assert_eq!(p.attribute_value("id"), Some("a"));
+/// assert_eq!(p.next_sibling().unwrap().string_value(), "world");
+/// assert_eq!(p.to_string(), r#"<p id="a">Hello</p>"#);
+/// ```
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    xoz: &'a Xoz,
+    node: Node,
+}
+
+/// ## Node reference
+///
+/// Bundle a node with the Xoz structure so methods can be called on it.
+impl Xoz {
+    /// Get a [`NodeRef`] for a node.
+    pub fn node_ref(&self, node: Node) -> NodeRef<'_> {
+        NodeRef { xoz: self, node }
+    }
+}
+
+impl<'a> NodeRef<'a> {
+    fn wrap(&self, node: Node) -> NodeRef<'a> {
+        NodeRef {
+            xoz: self.xoz,
+            node,
+        }
+    }
+
+    fn wrap_option(&self, node: Option<Node>) -> Option<NodeRef<'a>> {
+        node.map(|n| self.wrap(n))
+    }
+
+    fn wrap_iter(
+        &self,
+        nodes: impl Iterator<Item = Node> + 'a,
+    ) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        let xoz = self.xoz;
+        nodes.map(move |node| NodeRef { xoz, node })
+    }
+
+    /// The Xoz structure the node is in.
+    pub fn xoz(&self) -> &'a Xoz {
+        self.xoz
+    }
+
+    /// The node itself.
+    pub fn node(&self) -> Node {
+        self.node
+    }
+
+    // Information
+
+    /// See [`Xoz::node_type`].
+    pub fn node_type(&self) -> &'a NodeType<'a> {
+        self.xoz.node_type(self.node)
+    }
+
+    /// See [`Xoz::node_name`].
+    pub fn node_name(&self) -> Option<&'a NodeName<'a>> {
+        self.xoz.node_name(self.node)
+    }
+
+    /// See [`Xoz::node_full_name`].
+    pub fn full_name(&self) -> Option<String> {
+        self.xoz.node_full_name(self.node)
+    }
+
+    /// See [`Xoz::node_id`].
+    pub fn node_id(&self) -> NodeId {
+        self.xoz.node_id(self.node)
+    }
+
+    /// See [`Xoz::preorder`].
+    pub fn preorder(&self) -> usize {
+        self.xoz.preorder(self.node)
+    }
+
+    /// See [`Xoz::is_document`].
+    pub fn is_document(&self) -> bool {
+        self.xoz.is_document(self.node)
+    }
+
+    /// See [`Xoz::is_element`].
+    pub fn is_element(&self) -> bool {
+        self.xoz.is_element(self.node)
+    }
+
+    /// See [`Xoz::is_text`].
+    pub fn is_text(&self) -> bool {
+        self.xoz.is_text(self.node)
+    }
+
+    /// See [`Xoz::is_comment`].
+    pub fn is_comment(&self) -> bool {
+        self.xoz.is_comment(self.node)
+    }
+
+    /// See [`Xoz::is_processing_instruction`].
+    pub fn is_processing_instruction(&self) -> bool {
+        self.xoz.is_processing_instruction(self.node)
+    }
+
+    /// See [`Xoz::is_attribute`].
+    pub fn is_attribute(&self) -> bool {
+        self.xoz.is_attribute(self.node)
+    }
+
+    /// See [`Xoz::is_namespace`].
+    pub fn is_namespace(&self) -> bool {
+        self.xoz.is_namespace(self.node)
+    }
+
+    // Navigation
+
+    /// See [`Xoz::document_element`].
+    pub fn document_element(&self) -> NodeRef<'a> {
+        self.wrap(self.xoz.document_element(self.node))
+    }
+
+    /// See [`Xoz::parent`].
+    pub fn parent(&self) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.parent(self.node))
+    }
+
+    /// See [`Xoz::first_child`].
+    pub fn first_child(&self) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.first_child(self.node))
+    }
+
+    /// See [`Xoz::last_child`].
+    pub fn last_child(&self) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.last_child(self.node))
+    }
+
+    /// See [`Xoz::next_sibling`].
+    pub fn next_sibling(&self) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.next_sibling(self.node))
+    }
+
+    /// See [`Xoz::previous_sibling`].
+    pub fn previous_sibling(&self) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.previous_sibling(self.node))
+    }
+
+    /// See [`Xoz::nth_child`].
+    pub fn nth_child(&self, n: usize) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.nth_child(self.node, n))
+    }
+
+    /// See [`Xoz::child_count`].
+    pub fn child_count(&self) -> usize {
+        self.xoz.child_count(self.node)
+    }
+
+    /// See [`Xoz::depth`].
+    pub fn depth(&self) -> usize {
+        self.xoz.depth(self.node)
+    }
+
+    /// Whether this node is an ancestor of the other node.
+    ///
+    /// See [`Xoz::is_ancestor`].
+    pub fn is_ancestor_of(&self, other: NodeRef) -> bool {
+        self.xoz.is_ancestor(self.node, other.node)
+    }
+
+    /// See [`Xoz::typed_descendant`].
+    pub fn typed_descendant(&self, node_type: NodeType) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.typed_descendant(self.node, node_type))
+    }
+
+    /// See [`Xoz::typed_foll`].
+    pub fn typed_foll(&self, node_type: NodeType) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.typed_foll(self.node, node_type))
+    }
+
+    /// See [`Xoz::typed_ancestor`].
+    pub fn typed_ancestor(&self, node_type: NodeType) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.typed_ancestor(self.node, node_type))
+    }
+
+    // Text
+
+    /// See [`Xoz::string_value`].
+    pub fn string_value(&self) -> String {
+        self.xoz.string_value(self.node)
+    }
+
+    /// See [`Xoz::node_str`].
+    pub fn node_str(&self) -> Option<&'a str> {
+        self.xoz.node_str(self.node)
+    }
+
+    /// See [`Xoz::node_text`].
+    pub fn node_text(&self) -> Option<Cow<'a, str>> {
+        self.xoz.node_text(self.node)
+    }
+
+    /// See [`Xoz::text_str`].
+    pub fn text_str(&self) -> Option<&'a str> {
+        self.xoz.text_str(self.node)
+    }
+
+    // Attributes
+
+    /// See [`Xoz::attribute_node`].
+    pub fn attribute_node<'n>(&self, name: impl Into<NodeName<'n>>) -> Option<NodeRef<'a>> {
+        self.wrap_option(self.xoz.attribute_node(self.node, name))
+    }
+
+    /// See [`Xoz::attribute_value`].
+    pub fn attribute_value<'n>(&self, name: impl Into<NodeName<'n>>) -> Option<&'a str> {
+        self.xoz.attribute_value(self.node, name)
+    }
+
+    /// See [`Xoz::attribute_text`].
+    pub fn attribute_text<'n>(&self, name: impl Into<NodeName<'n>>) -> Option<Cow<'a, str>> {
+        self.xoz.attribute_text(self.node, name)
+    }
+
+    /// See [`Xoz::attribute_entries`].
+    pub fn attribute_entries(&self) -> impl Iterator<Item = (&'a NodeName<'a>, &'a str)> + 'a {
+        self.xoz.attribute_entries(self.node)
+    }
+
+    // Iteration
+
+    /// See [`Xoz::children`].
+    pub fn children(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.children(self.node))
+    }
+
+    /// See [`Xoz::attributes`].
+    pub fn attributes(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.attributes(self.node))
+    }
+
+    /// See [`Xoz::ancestors`].
+    pub fn ancestors(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.ancestors(self.node))
+    }
+
+    /// See [`Xoz::descendants`].
+    pub fn descendants(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.descendants(self.node))
+    }
+
+    /// See [`Xoz::following_siblings`].
+    pub fn following_siblings(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.following_siblings(self.node))
+    }
+
+    /// See [`Xoz::preceding_siblings`].
+    pub fn preceding_siblings(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.preceding_siblings(self.node))
+    }
+
+    /// See [`Xoz::typed_descendants`].
+    pub fn typed_descendants(&self, node_type: NodeType) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.typed_descendants(self.node, node_type))
+    }
+
+    /// See [`Xoz::typed_children`].
+    pub fn typed_children(&self, node_type: NodeType) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        self.wrap_iter(self.xoz.typed_children(self.node, node_type))
+    }
+}
+
+impl PartialEq for NodeRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.xoz, other.xoz) && self.node == other.node
+    }
+}
+
+impl Eq for NodeRef<'_> {}
+
+impl PartialEq<Node> for NodeRef<'_> {
+    fn eq(&self, other: &Node) -> bool {
+        self.node == *other
+    }
+}
+
+impl From<NodeRef<'_>> for Node {
+    fn from(node_ref: NodeRef<'_>) -> Self {
+        node_ref.node
+    }
+}
+
+impl fmt::Display for NodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.xoz.serialize_to_string(self.node))
+    }
+}
+
+impl fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // nodes without a name are shown by their kind, as in a path
+        let name = self.full_name().unwrap_or_else(|| {
+            match self.node_type() {
+                NodeType::Document => "document-node()",
+                NodeType::Text => "text()",
+                NodeType::Comment => "comment()",
+                NodeType::ProcessingInstruction => "processing-instruction()",
+                _ => "namespace-node()",
+            }
+            .to_string()
+        });
+        f.debug_struct("NodeRef")
+            .field("name", &name)
+            .field("path", &self.xoz.path(self.node))
+            .finish()
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{error::QNameError, NodeName};
+
 use super::core::{Node, Xoz};
 
 /// ## Namespace
@@ -40,6 +42,152 @@ impl Xoz {
         document.prefix_for_namespace(node.document_node, uri)
     }
 
+    /// Given a prefix, return the namespace URI it maps to for this node.
+    ///
+    /// This walks up the tree to find the nearest declaration of the prefix.
+    /// The empty prefix gives the default namespace, which is the empty
+    /// namespace if none is declared. The `xml` prefix always exists.
+    ///
+    /// Returns [`None`] if the prefix isn't declared.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz
+    ///     .parse_str(r#"<doc xmlns:ex="http://example.com"><a xmlns="http://default.com"/></doc>"#)
+    ///     .unwrap();
+    /// let doc = xoz.document_element(root);
+    /// let a = xoz.first_child(doc).unwrap();
+    ///
+    /// assert_eq!(xoz.namespace_for_prefix(a, b"ex"), Some(&b"http://example.com"[..]));
+    /// assert_eq!(xoz.namespace_for_prefix(a, b""), Some(&b"http://default.com"[..]));
+    /// assert_eq!(xoz.namespace_for_prefix(doc, b""), Some(&b""[..]));
+    /// assert_eq!(xoz.namespace_for_prefix(doc, b"other"), None);
+    /// ```
+    pub fn namespace_for_prefix(&self, node: Node, prefix: &[u8]) -> Option<&[u8]> {
+        let document = self.document(node.document_id);
+        document.namespace_for_prefix(node.document_node, prefix)
+    }
+
+    /// Whether a prefix is declared for this node.
+    ///
+    /// See [`Xoz::namespace_for_prefix`].
+    pub fn is_prefix_defined(&self, node: Node, prefix: &[u8]) -> bool {
+        let document = self.document(node.document_id);
+        document.is_prefix_defined(node.document_node, prefix)
+    }
+
+    /// Get an iterator over the namespaces in scope for this node.
+    ///
+    /// This iterates over prefix, uri tuples: those declared on this node,
+    /// then those declared on its ancestors which aren't redeclared further
+    /// down. A default namespace undeclared with `xmlns=""` isn't in scope.
+    /// The `xml` prefix always comes last.
+    ///
+    /// For nodes other than elements this is the scope of their parent
+    /// element.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz
+    ///     .parse_str(r#"<doc xmlns:a="http://a.com" xmlns:b="http://b.com"><p xmlns:a="http://a2.com"/></doc>"#)
+    ///     .unwrap();
+    /// let doc = xoz.document_element(root);
+    /// let p = xoz.first_child(doc).unwrap();
+    ///
+    /// let in_scope = xoz.namespaces_in_scope(p).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     in_scope,
+    ///     vec![
+    ///         (&b"a"[..], &b"http://a2.com"[..]),
+    ///         (&b"b"[..], &b"http://b.com"[..]),
+    ///         (&b"xml"[..], &b"http://www.w3.org/XML/1998/namespace"[..]),
+    ///     ]
+    /// );
+    /// ```
+    pub fn namespaces_in_scope(&self, node: Node) -> impl Iterator<Item = (&[u8], &[u8])> + '_ {
+        let document = self.document(node.document_id);
+        document.namespaces_in_scope(node.document_node).into_iter()
+    }
+
+    /// Get an iterator over the namespaces this node inherits from its
+    /// ancestors.
+    ///
+    /// These are the namespaces in scope for the node that it doesn't declare
+    /// itself, not including the `xml` prefix. When serializing the node
+    /// on its own, these are the declarations that need to be added to it.
+    pub fn inherited_prefixes(&self, node: Node) -> impl Iterator<Item = (&[u8], &[u8])> + '_ {
+        let document = self.document(node.document_id);
+        document.inherited_prefixes(node.document_node).into_iter()
+    }
+
+    /// Resolve a QName, such as `ex:thing`, using the namespaces in scope for
+    /// a node.
+    ///
+    /// This is useful for attributes and text that contain QNames. An
+    /// unprefixed name is in the default namespace, if any. Surrounding
+    /// whitespace is ignored.
+    ///
+    /// The result can be used to construct a [`NodeType`](crate::NodeType)
+    /// to look up elements or attributes with this name.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeName};
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz
+    ///     .parse_str(r#"<doc xmlns:ex="http://example.com"><ref to="ex:thing"/></doc>"#)
+    ///     .unwrap();
+    /// let doc = xoz.document_element(root);
+    ///
+    /// let name = xoz.resolve_qname(doc, "ex:thing").unwrap();
+    /// assert_eq!(name, NodeName::new("http://example.com", "thing"));
+    ///
+    /// assert!(xoz.resolve_qname(doc, "other:thing").is_err());
+    /// ```
+    pub fn resolve_qname<'a>(
+        &'a self,
+        node: Node,
+        qname: &'a str,
+    ) -> Result<NodeName<'a>, QNameError> {
+        let document = self.document(node.document_id);
+        document.resolve_qname(node.document_node, qname)
+    }
+
+    /// Resolve the QName in the value of an attribute of an element.
+    ///
+    /// The QName is resolved using the namespaces in scope for the element.
+    /// Returns `Ok(None)` if the element has no such attribute.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeName, NodeType};
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz
+    ///     .parse_str(r#"<doc xmlns:ex="http://example.com"><ex:thing/><ref to="ex:thing"/></doc>"#)
+    ///     .unwrap();
+    /// let doc = xoz.document_element(root);
+    /// let thing = xoz.first_child(doc).unwrap();
+    /// let reference = xoz.next_sibling(thing).unwrap();
+    ///
+    /// let name = xoz.attribute_qname(reference, "to").unwrap().unwrap();
+    /// let found = xoz.typed_descendant(root, NodeType::Element(name));
+    /// assert_eq!(found, Some(thing));
+    ///
+    /// assert_eq!(xoz.attribute_qname(reference, "missing"), Ok(None));
+    /// ```
+    pub fn attribute_qname<'a>(
+        &'a self,
+        node: Node,
+        name: impl Into<NodeName<'a>>,
+    ) -> Result<Option<NodeName<'a>>, QNameError> {
+        self.attribute_text(node, name)
+            .map(|value| {
+                self.resolve_qname(node, &value)
+                    .map(|name| name.into_owned())
+            })
+            .transpose()
+    }
+
     /// Prefix for a node
     ///
     /// Only element and attributes can have prefixes.
@@ -0,0 +1,230 @@
+use std::fmt::Write;
+
+use crate::{NodeName, NodeType};
+
+use super::core::{Node, Xoz};
+
+/// ## Path
+///
+/// Canonical paths that identify nodes, and navigating back from them.
+impl Xoz {
+    /// A canonical XPath expression that selects this node from the root.
+    ///
+    /// Each step gives the position of the node among its siblings of the
+    /// same name or kind, such as `/doc[1]/chapter[3]/p[2]/@id` or
+    /// `/doc[1]/p[1]/text()[2]`. The root node itself is `/`.
+    ///
+    /// A name in a namespace uses a prefix in scope for it where the step is
+    /// taken, so `ex:chapter`. For an element that is the scope of its
+    /// parent. If there is no such prefix, because it is the default
+    /// namespace or the element declares or rebinds the prefix itself, the
+    /// name is written in the braced form `Q{http://example.com}chapter`.
+    /// Namespace nodes are `namespace::prefix`.
+    ///
+    /// [`Xoz::resolve_path`] navigates back from a path to the node.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<doc><p/><p id="x">Hello</p></doc>"#).unwrap();
+    /// let doc = xoz.document_element(root);
+    /// let p2 = xoz.last_child(doc).unwrap();
+    /// let id = xoz.attribute_node(p2, "id").unwrap();
+    /// let text = xoz.first_child(p2).unwrap();
+    ///
+    /// assert_eq!(xoz.path(p2), "/doc[1]/p[2]");
+    /// assert_eq!(xoz.path(id), "/doc[1]/p[2]/@id");
+    /// assert_eq!(xoz.path(text), "/doc[1]/p[2]/text()[1]");
+    /// assert_eq!(xoz.path(root), "/");
+    /// ```
+    pub fn path(&self, node: Node) -> String {
+        let mut steps = self
+            .ancestors_or_self(node)
+            .filter(|n| !self.is_document(*n))
+            .collect::<Vec<_>>();
+        if steps.is_empty() {
+            return "/".to_string();
+        }
+        steps.reverse();
+        let mut path = String::new();
+        for step in steps {
+            path.push('/');
+            self.write_step(&mut path, step);
+        }
+        path
+    }
+
+    fn write_step(&self, path: &mut String, node: Node) {
+        let node_type = self.node_type(node);
+        match node_type {
+            NodeType::Attribute(_) => {
+                path.push('@');
+                self.write_name(path, node, node);
+                return;
+            }
+            NodeType::Namespace(namespace) => {
+                path.push_str("namespace::");
+                path.push_str(
+                    std::str::from_utf8(namespace.prefix()).expect("prefix should be UTF-8"),
+                );
+                return;
+            }
+            NodeType::Element(_) => {
+                let parent = self.parent(node).expect("element has parent");
+                self.write_name(path, node, parent)
+            }
+            NodeType::Text => path.push_str("text()"),
+            NodeType::Comment => path.push_str("comment()"),
+            NodeType::ProcessingInstruction => path.push_str("processing-instruction()"),
+            NodeType::Document | NodeType::Namespaces | NodeType::Attributes => {
+                unreachable!("not a step in a path")
+            }
+        }
+        // the position among siblings of the same type, so of the same
+        // expanded name for elements
+        let position = self
+            .document(node.document_id)
+            .typed_position(node.document_node);
+        write!(path, "[{}]", position).expect("writing to a String cannot fail");
+    }
+
+    // write the name of the node with a prefix in scope at scope, if it
+    // still maps to the namespace of the name there
+    fn write_name(&self, path: &mut String, node: Node, scope: Node) {
+        let name = self.node_name(node).expect("element or attribute has name");
+        let local_name =
+            std::str::from_utf8(name.local_name()).expect("local name should be UTF-8");
+        if name.namespace().is_empty() {
+            path.push_str(local_name);
+            return;
+        }
+        let prefix = self
+            .prefix_for_namespace(scope, name.namespace())
+            .filter(|prefix| self.namespace_for_prefix(scope, prefix) == Some(name.namespace()));
+        match prefix {
+            Some(prefix) if !prefix.is_empty() => {
+                path.push_str(std::str::from_utf8(prefix).expect("prefix should be UTF-8"));
+                path.push(':');
+                path.push_str(local_name);
+            }
+            _ => {
+                write!(
+                    path,
+                    "Q{{{}}}{}",
+                    std::str::from_utf8(name.namespace()).expect("namespace should be UTF-8"),
+                    local_name
+                )
+                .expect("writing to a String cannot fail");
+            }
+        }
+    }
+
+    /// Navigate to the node identified by a path from the root.
+    ///
+    /// This accepts paths as produced by [`Xoz::path`]. Any node of the
+    /// document can be given as the starting point; navigation always starts
+    /// at its root. A step without a position is the first of its kind. A
+    /// prefix is resolved using the namespaces in scope where the step is
+    /// taken, so a prefix that the selected element declares itself cannot be
+    /// used; write its name in the braced form `Q{namespace}local` instead.
+    ///
+    /// Returns [`None`] if the path is not valid or no node is found.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<doc><p/><p id="x">Hello</p></doc>"#).unwrap();
+    /// let doc = xoz.document_element(root);
+    /// let p2 = xoz.last_child(doc).unwrap();
+    ///
+    /// assert_eq!(xoz.resolve_path(root, "/doc[1]/p[2]"), Some(p2));
+    /// assert_eq!(xoz.resolve_path(root, "/doc/p[2]/@id"), xoz.attribute_node(p2, "id"));
+    /// assert_eq!(xoz.resolve_path(root, "/doc[1]/p[3]"), None);
+    ///
+    /// // paths round trip
+    /// for node in xoz.descendants(root) {
+    ///     assert_eq!(xoz.resolve_path(root, &xoz.path(node)), Some(node));
+    /// }
+    /// ```
+    pub fn resolve_path(&self, root: Node, path: &str) -> Option<Node> {
+        let document = self.document(root.document_id);
+        let mut node = document.new_node(document.root());
+        let rest = path.strip_prefix('/')?;
+        if rest.is_empty() {
+            return Some(node);
+        }
+        for step in split_steps(rest)? {
+            node = self.resolve_step(node, step)?;
+        }
+        Some(node)
+    }
+
+    fn resolve_step(&self, node: Node, step: &str) -> Option<Node> {
+        if let Some(name) = step.strip_prefix('@') {
+            let name = self.resolve_step_name(node, name)?;
+            return self.attribute_node(node, name);
+        }
+        if let Some(prefix) = step.strip_prefix("namespace::") {
+            let namespaces = self.namespaces_child(node)?;
+            return self.children(namespaces).find(|n| {
+                matches!(self.node_type(*n), NodeType::Namespace(namespace)
+                    if namespace.prefix() == prefix.as_bytes())
+            });
+        }
+        let (test, position) = match step.strip_suffix(']') {
+            Some(step) => {
+                let (test, position) = step.rsplit_once('[')?;
+                (test, position.parse::<usize>().ok()?.checked_sub(1)?)
+            }
+            None => (step, 0),
+        };
+        let node_type = match test {
+            "text()" => NodeType::Text,
+            "comment()" => NodeType::Comment,
+            "processing-instruction()" => NodeType::ProcessingInstruction,
+            name => NodeType::Element(self.resolve_step_name(node, name)?),
+        };
+        self.typed_children(node, node_type).nth(position)
+    }
+
+    fn resolve_step_name<'a>(&'a self, node: Node, name: &'a str) -> Option<NodeName<'a>> {
+        if let Some(braced) = name.strip_prefix("Q{") {
+            let (namespace, local_name) = braced.split_once('}')?;
+            return Some(NodeName::new(namespace, local_name));
+        }
+        match name.split_once(':') {
+            Some((prefix, local_name)) => {
+                let namespace = self.namespace_for_prefix(node, prefix.as_bytes())?;
+                Some(NodeName::from_bytes(namespace, local_name.as_bytes()))
+            }
+            None => Some(NodeName::new("", name)),
+        }
+    }
+}
+
+// split a path into its steps on `/`, except within the braces of a
+// `Q{...}` name, as a namespace URI can contain `/` too
+fn split_steps(path: &str) -> Option<Vec<&str>> {
+    let mut steps = Vec::new();
+    let mut start = 0;
+    let mut in_braces = false;
+    for (i, c) in path.char_indices() {
+        match c {
+            '{' if !in_braces => in_braces = true,
+            '}' if in_braces => in_braces = false,
+            '/' if !in_braces => {
+                steps.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if in_braces {
+        return None;
+    }
+    steps.push(&path[start..]);
+    if steps.iter().any(|step| step.is_empty()) {
+        return None;
+    }
+    Some(steps)
+}
@@ -0,0 +1,294 @@
+use std::ops::Range;
+
+use regex::Regex;
+
+use crate::{error::XPathError, NodeSet, NodeType, WordQuery};
+
+use super::core::{Node, Xoz};
+
+/// A search hit with the text around it, as shown in search results.
+///
+/// Obtain it with [`Xoz::snippet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    node: Node,
+    range: Range<usize>,
+    before: String,
+    matched: String,
+    after: String,
+    path: Vec<Node>,
+}
+
+impl Snippet {
+    /// The text or attribute node of the hit.
+    pub fn node(&self) -> Node {
+        self.node
+    }
+
+    /// The byte range of the hit within the text of its node.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The context text before the hit.
+    pub fn before(&self) -> &str {
+        &self.before
+    }
+
+    /// The text of the hit itself.
+    pub fn matched(&self) -> &str {
+        &self.matched
+    }
+
+    /// The context text after the hit.
+    pub fn after(&self) -> &str {
+        &self.after
+    }
+
+    /// The path to the node of the hit, starting with the document node and
+    /// ending with the node itself.
+    pub fn path(&self) -> &[Node] {
+        &self.path
+    }
+}
+
+/// ## Search
+///
+/// Searching through the text content of a document.
+impl Xoz {
+    /// Select nodes with an XPath expression.
+    ///
+    /// The expression is an XPath 1.0 location path, such as
+    /// `//chapter/title`, or a union of them with `|`. A relative path is
+    /// evaluated with the given node as the context node; an absolute path
+    /// from the root of its document. Abbreviated syntax (`//`, `@`, `.`,
+    /// `..` and `*`) is supported. Prefixes are resolved using the
+    /// namespaces in scope for the node, or for the document element if the
+    /// node is the root.
+    ///
+    /// The expression is compiled into a tree automaton that finds all
+    /// nodes selected along forward axes in a single run over the tree. A
+    /// step along the parent, ancestor, preceding or following axes (and
+    /// their variants) is taken by walking from the nodes selected before
+    /// it. The nodes are returned in document order.
+    ///
+    /// Where a descendant step only looks for particular names or kinds of
+    /// node, as in `//figure//caption`, the run jumps from one such node to
+    /// the next using the structure index, so it takes time in proportion
+    /// to the nodes found rather than to the size of the document.
+    ///
+    /// Predicates can test whether a relative path selects anything, and
+    /// compare the string value of what it selects with a literal, as in
+    /// `//p[@type='note']` or `//p[.='foo']`; they can be combined with
+    /// `and`, `or` and `not()`. Within predicates only forward axes are
+    /// supported.
+    ///
+    /// Node tests can be names, the wildcards `*` and `ex:*`, or the kind
+    /// tests `node()`, `text()`, `comment()` and `processing-instruction()`,
+    /// with or without a target.
+    ///
+    /// Positional predicates, functions other than `not()`, other
+    /// comparisons and the namespace axis are not supported; these give
+    /// [`XPathError::Unsupported`].
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz
+    ///     .parse_str("<book><chapter><title>A</title></chapter><chapter><title>B</title></chapter></book>")
+    ///     .unwrap();
+    ///
+    /// let titles = xoz.select(root, "//chapter/title").unwrap();
+    /// let texts = titles.iter().map(|n| xoz.string_value(n)).collect::<Vec<_>>();
+    /// assert_eq!(texts, vec!["A", "B"]);
+    /// ```
+    pub fn select(&self, node: Node, expr: &str) -> Result<NodeSet, XPathError> {
+        let document = self.document(node.document_id);
+        let selected = document.select(node.document_node, expr)?;
+        Ok(selected.into_iter().map(|n| document.new_node(n)).collect())
+    }
+
+    /// Search the text and attribute nodes under a node with a regular
+    /// expression.
+    ///
+    /// This includes the node itself and the attributes of elements. For each
+    /// match it yields the text or attribute node and the byte range of the
+    /// match within its text. A node can occur multiple times if it has
+    /// multiple matches. Matches are yielded in document order.
+    ///
+    /// This goes through the stored text directly rather than visiting the
+    /// nodes one by one, so it's efficient even for huge documents.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// use regex::Regex;
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<doc><p title="TODO">A TODO here</p><p>fine</p></doc>"#).unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let p = xoz.first_child(doc_el).unwrap();
+    /// let title = xoz.attribute_node(p, "title").unwrap();
+    /// let text = xoz.first_child(p).unwrap();
+    ///
+    /// let regex = Regex::new("TODO").unwrap();
+    /// let found = xoz.search_text_regex(root, &regex).collect::<Vec<_>>();
+    /// assert_eq!(found, vec![(title, 0..4), (text, 2..6)]);
+    /// ```
+    pub fn search_text_regex<'a>(
+        &'a self,
+        node: Node,
+        regex: &'a Regex,
+    ) -> impl Iterator<Item = (Node, Range<usize>)> + 'a {
+        let document = self.document(node.document_id);
+        document
+            .search_text_regex(node.document_node, regex)
+            .map(move |(n, range)| (document.new_node(n), range))
+    }
+
+    /// Search the words in the text nodes under a node.
+    ///
+    /// For each match of the [`WordQuery`] it gives the text node and the
+    /// byte offset within its text where the match starts, in document order.
+    /// A match has to end under the node. Attributes, comments and processing
+    /// instructions are not searched.
+    ///
+    /// A match can continue from one text node into the next as long as both
+    /// are in the same block: the nearest ancestor for which `is_block`
+    /// returns true, as for [`Xoz::snippet`]. So a phrase goes on past inline
+    /// markup such as `<em>`, but not from one paragraph into the next. If
+    /// there is no such ancestor, the block is the whole document.
+    ///
+    /// The word index of a document is built the first time you search it,
+    /// and kept for later searches.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType, WordQuery};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<doc><p>Hello World</p><p>Say hello, world</p></doc>").unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let p1 = xoz.first_child(doc_el).unwrap();
+    /// let p2 = xoz.next_sibling(p1).unwrap();
+    /// let text1 = xoz.first_child(p1).unwrap();
+    /// let text2 = xoz.first_child(p2).unwrap();
+    ///
+    /// let is_block = |node_type: &NodeType| node_type == &NodeType::element("p");
+    /// let found = xoz.search_words(root, &WordQuery::phrase("hello world"), is_block);
+    /// assert_eq!(found, vec![(text1, 0), (text2, 4)]);
+    /// let found = xoz.search_words(root, &WordQuery::near("world", "say", 1), is_block);
+    /// assert_eq!(found, vec![]);
+    /// ```
+    pub fn search_words(
+        &self,
+        node: Node,
+        query: &WordQuery,
+        is_block: impl Fn(&NodeType) -> bool,
+    ) -> Vec<(Node, usize)> {
+        let document = self.document(node.document_id);
+        document
+            .search_words(node.document_node, query, is_block)
+            .into_iter()
+            .map(|(n, offset)| (document.new_node(n), offset))
+            .collect()
+    }
+
+    /// Search the words in the text nodes under a node, and give the nodes
+    /// of [`NodeType`] that contain the matches.
+    ///
+    /// For each match of the [`WordQuery`] this is the nearest ancestor of the
+    /// node type that contains the whole match. Each node is given once, in
+    /// document order. Blocks limit matches as for [`Xoz::search_words`].
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType, WordQuery};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(concat!(
+    ///     "<doc><section><p>The <em>quick</em> fox</p></section>",
+    ///     "<section><p>slow</p></section></doc>"
+    /// )).unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let section = xoz.first_child(doc_el).unwrap();
+    ///
+    /// let section_type = NodeType::element("section");
+    /// let is_block = |node_type: &NodeType| node_type == &NodeType::element("p");
+    /// let found = xoz.search_words_in(root, &WordQuery::phrase("quick fox"), section_type, is_block);
+    /// assert_eq!(found, vec![section]);
+    /// ```
+    pub fn search_words_in(
+        &self,
+        node: Node,
+        query: &WordQuery,
+        node_type: NodeType,
+        is_block: impl Fn(&NodeType) -> bool,
+    ) -> Vec<Node> {
+        let document = self.document(node.document_id);
+        document
+            .search_words_in(node.document_node, query, &node_type, is_block)
+            .into_iter()
+            .map(|n| document.new_node(n))
+            .collect()
+    }
+
+    /// Create a [`Snippet`] for a search hit, with up to `before` characters
+    /// of context before it and `after` characters after it.
+    ///
+    /// The hit is given as a text or attribute node and a byte range within
+    /// its text, as produced by [`Xoz::search_text_regex`].
+    ///
+    /// The context continues into the surrounding text nodes, but not past
+    /// the nearest ancestor for which `is_block` returns true, so that the
+    /// context of a hit in a paragraph doesn't include the paragraph before
+    /// it. If there is no such ancestor, the context can come from the whole
+    /// document. The context of an attribute hit only comes from the
+    /// attribute value itself.
+    ///
+    /// Returns `None` if the node has no text, such as an element, or if the
+    /// range is beyond the end of its text or not on character boundaries.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, NodeType};
+    ///
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<doc><p>First.</p><p>A <em>big</em> hit in context</p></doc>").unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let p = xoz.last_child(doc_el).unwrap();
+    /// let text = xoz.last_child(p).unwrap();
+    ///
+    /// let is_block = |node_type: &NodeType| node_type == &NodeType::element("p");
+    /// let snippet = xoz.snippet(text, 1..4, 6, 3, is_block).unwrap();
+    /// assert_eq!(snippet.before(), "A big ");
+    /// assert_eq!(snippet.matched(), "hit");
+    /// assert_eq!(snippet.after(), " in");
+    /// assert_eq!(snippet.path(), &[root, doc_el, p, text]);
+    ///
+    /// assert!(xoz.snippet(p, 0..1, 6, 3, is_block).is_none());
+    /// assert!(xoz.snippet(text, 1..100, 6, 3, is_block).is_none());
+    /// ```
+    pub fn snippet(
+        &self,
+        node: Node,
+        range: Range<usize>,
+        before: usize,
+        after: usize,
+        is_block: impl Fn(&NodeType) -> bool,
+    ) -> Option<Snippet> {
+        let document = self.document(node.document_id);
+        let (before, after) =
+            document.snippet_context(node.document_node, range.clone(), before, after, is_block)?;
+        let matched = document.node_text(node.document_node)?[range.clone()].to_string();
+        let mut path = document
+            .ancestors_or_self(node.document_node)
+            .map(|n| document.new_node(n))
+            .collect::<Vec<_>>();
+        path.reverse();
+        Some(Snippet {
+            node,
+            range,
+            before,
+            matched,
+            after,
+            path,
+        })
+    }
+}
@@ -1,10 +1,19 @@
-use crate::ProcessingInstruction;
+use std::{borrow::Cow, fmt};
+
+#[cfg(doc)]
+use crate::TextStorage;
+use crate::{ProcessingInstruction, ValueId};
 
 use super::core::{Node, Xoz};
 
 /// ## Text
 ///
 /// These are functions to obtain text content information from the tree.
+///
+/// Text is borrowed from the document. With [`TextStorage::Compressed`] text
+/// is only kept decompressed in a small cache, so it can't be borrowed, and
+/// the functions that give `&str` panic. Use [`Xoz::node_text`] and
+/// [`Xoz::attribute_text`] instead, which work with any text storage.
 impl Xoz {
     /// Text node string.
     ///
@@ -41,7 +50,7 @@ impl Xoz {
     }
 
     /// Get [`ProcessingInstruction`] if this is a processing instruction node.
-    pub fn processing_instruction(&self, node: Node) -> Option<ProcessingInstruction> {
+    pub fn processing_instruction(&self, node: Node) -> Option<ProcessingInstruction<'_>> {
         let document = self.document(node.document_id);
         document.processing_instruction(node.document_node)
     }
@@ -68,6 +77,156 @@ impl Xoz {
         document.string_value(node.document_node)
     }
 
+    /// The string value of a node as a sequence of string slices.
+    ///
+    /// Concatenated, these form the same string as [`Xoz::string_value`], but
+    /// no new string is allocated. For the root node and element nodes there
+    /// is a slice for each descendant text node; these are found directly
+    /// without traversing the other descendants.
+    ///
+    /// As this borrows the text, it panics with [`TextStorage::Compressed`].
+    /// Use [`Xoz::write_string_value`] for such documents.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<p a="attr">Hello <em>big</em> world<!--no--></p>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    ///
+    /// let parts = xoz.string_value_parts(p).collect::<Vec<_>>();
+    /// assert_eq!(parts, vec!["Hello ", "big", " world"]);
+    /// ```
+    pub fn string_value_parts(&self, node: Node) -> impl Iterator<Item = &str> + '_ {
+        let document = self.document(node.document_id);
+        document.string_value_parts(node.document_node)
+    }
+
+    /// Write the string value of a node.
+    ///
+    /// This writes the same string as [`Xoz::string_value`] gives, without
+    /// allocating it first.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str("<p>Hello <em>big</em> world</p>").unwrap();
+    /// let p = xoz.document_element(root);
+    ///
+    /// let mut s = String::new();
+    /// xoz.write_string_value(p, &mut s).unwrap();
+    /// assert_eq!(s, "Hello big world");
+    /// ```
+    pub fn write_string_value(&self, node: Node, w: impl fmt::Write) -> fmt::Result {
+        let document = self.document(node.document_id);
+        document.write_string_value(node.document_node, w)
+    }
+
+    /// Find the text node at a byte offset in the plain text of a document.
+    ///
+    /// The plain text is the text of all text nodes of the document
+    /// concatenated, which is the string value of its root node, as given
+    /// by [`Xoz::string_value`]. Attributes, comments and processing
+    /// instructions aren't part of it. The document is given by its root
+    /// node; for other nodes this returns `None`.
+    ///
+    /// This gives the text node and the offset within its text. An offset at
+    /// the boundary of two text nodes is at the start of the second one,
+    /// except at the end of the plain text, which is at the end of the last
+    /// text node. Returns `None` if the offset is beyond the end of the plain
+    /// text.
+    ///
+    /// The first time you use this or [`Xoz::text_offset`] on a document, it
+    /// builds an index of where its text nodes start, and keeps it for later
+    /// calls. It is included in [`Xoz::heap_size`].
+    ///
+    /// [`Xoz::text_offset`] is the inverse of this.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<p a="x">Hello <em>big</em> world</p>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    /// let hello = xoz.first_child(p).unwrap();
+    /// let em = xoz.next_sibling(hello).unwrap();
+    /// let big = xoz.first_child(em).unwrap();
+    ///
+    /// // the plain text is "Hello big world"
+    /// assert_eq!(xoz.text_offset_node(root, 0), Some((hello, 0)));
+    /// assert_eq!(xoz.text_offset_node(root, 6), Some((big, 0)));
+    /// assert_eq!(xoz.text_offset_node(root, 7), Some((big, 1)));
+    /// assert_eq!(xoz.text_offset_node(root, 100), None);
+    /// assert_eq!(xoz.text_offset_node(p, 0), None);
+    /// ```
+    pub fn text_offset_node(&self, root: Node, offset: usize) -> Option<(Node, usize)> {
+        let document = self.document(root.document_id);
+        if !document.is_document(root.document_node) {
+            return None;
+        }
+        document
+            .text_offset_node(offset)
+            .map(|(n, offset)| (document.new_node(n), offset))
+    }
+
+    /// Give the byte offset in the plain text of a document of an offset
+    /// within a node.
+    ///
+    /// The node can be a text node, or an element or root node, in which
+    /// case the offset is within its string value. See
+    /// [`Xoz::text_offset_node`] for what the plain text is.
+    ///
+    /// Returns `None` for other nodes, or if the offset is beyond the end of
+    /// the node's text.
+    ///
+    /// ```rust
+    /// use xoz::Xoz;
+    /// let mut xoz = Xoz::new();
+    /// let root = xoz.parse_str(r#"<p a="x">Hello <em>big</em> world</p>"#).unwrap();
+    /// let p = xoz.document_element(root);
+    /// let hello = xoz.first_child(p).unwrap();
+    /// let em = xoz.next_sibling(hello).unwrap();
+    /// let big = xoz.first_child(em).unwrap();
+    ///
+    /// assert_eq!(xoz.text_offset(big, 1), Some(7));
+    /// assert_eq!(xoz.text_offset(em, 3), Some(9));
+    /// assert_eq!(xoz.text_offset(em, 4), None);
+    /// assert_eq!(xoz.text_offset(xoz.attribute_node(p, "a").unwrap(), 0), None);
+    /// ```
+    pub fn text_offset(&self, node: Node, offset: usize) -> Option<usize> {
+        let document = self.document(node.document_id);
+        document.text_offset(node.document_node, offset)
+    }
+
+    /// Get the [`ValueId`] of the text value of a node.
+    ///
+    /// This only exists for documents parsed with
+    /// [`TextStorage::Deduplicated`](crate::TextStorage::Deduplicated). It
+    /// works for text, attribute, comment and processing instruction nodes;
+    /// for other nodes it returns `None`.
+    ///
+    /// Nodes in the same document with equal values have the same value id,
+    /// so you can compare and group values without accessing their text.
+    ///
+    /// ```rust
+    /// use xoz::{Xoz, TextStorage};
+    /// let mut xoz = Xoz::with_text_storage(TextStorage::Deduplicated);
+    /// let root = xoz.parse_str(r#"<doc><a type="string"/><b type="int"/><c type="string"/></doc>"#).unwrap();
+    /// let doc_el = xoz.document_element(root);
+    /// let a = xoz.first_child(doc_el).unwrap();
+    /// let b = xoz.next_sibling(a).unwrap();
+    /// let c = xoz.next_sibling(b).unwrap();
+    /// let a_type = xoz.attribute_node(a, "type").unwrap();
+    /// let b_type = xoz.attribute_node(b, "type").unwrap();
+    /// let c_type = xoz.attribute_node(c, "type").unwrap();
+    ///
+    /// assert_eq!(xoz.value_id(a_type), xoz.value_id(c_type));
+    /// assert_ne!(xoz.value_id(a_type), xoz.value_id(b_type));
+    /// assert_eq!(xoz.value_id(a), None);
+    /// ```
+    pub fn value_id(&self, node: Node) -> Option<ValueId> {
+        let document = self.document(node.document_id);
+        document.value_id(node.document_node)
+    }
+
     /// Get the string content of a node.
     ///
     /// This works for text, attribute, comment, and processing instruction
@@ -76,4 +235,24 @@ impl Xoz {
         let document = self.document(node.document_id);
         document.node_str(node.document_node)
     }
+
+    /// Get the text content of a node, with any text storage.
+    ///
+    /// This is like [`Xoz::node_str`], but it also works with
+    /// [`TextStorage::Compressed`], in which case it gives a copy of the
+    /// text. With other text storage the text is borrowed.
+    ///
+    /// ```rust
+    /// use xoz::{TextStorage, Xoz};
+    /// let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    /// let root = xoz.parse_str("<p>Example</p>").unwrap();
+    /// let p = xoz.document_element(root);
+    /// let text = xoz.first_child(p).unwrap();
+    /// assert_eq!(xoz.node_text(text).as_deref(), Some("Example"));
+    /// assert_eq!(xoz.node_text(p), None);
+    /// ```
+    pub fn node_text(&self, node: Node) -> Option<Cow<'_, str>> {
+        let document = self.document(node.document_id);
+        document.node_text(node.document_node)
+    }
 }
@@ -0,0 +1,724 @@
+// A parser for XPath 1.0 location paths, producing the location path AST that
+// the automaton compiler translates. Abbreviated syntax is expanded as the
+// specification defines it, so `//` becomes a `descendant-or-self::node()`
+// step.
+
+use crate::{
+    error::XPathError,
+    mta_compiler::{Axis, Core, LocationPath, LocationStep, NodeTest, Pred},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Slash,
+    DoubleSlash,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    At,
+    DoubleColon,
+    Comma,
+    Pipe,
+    Dot,
+    DoubleDot,
+    Star,
+    Equals,
+    NotEquals,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Literal(String),
+    Number(f64),
+    Name(String),
+    PrefixedName(String, String),
+    PrefixWildcard(String),
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '\u{B7}')
+}
+
+fn tokenize(expr: &str) -> Result<Vec<(Token, usize)>, XPathError> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let rest = &expr[start..];
+        let symbol = [
+            ("//", Token::DoubleSlash),
+            ("::", Token::DoubleColon),
+            ("..", Token::DoubleDot),
+            ("!=", Token::NotEquals),
+            ("<=", Token::LessEqual),
+            (">=", Token::GreaterEqual),
+            ("/", Token::Slash),
+            ("[", Token::LeftBracket),
+            ("]", Token::RightBracket),
+            ("(", Token::LeftParen),
+            (")", Token::RightParen),
+            ("@", Token::At),
+            (",", Token::Comma),
+            ("|", Token::Pipe),
+            ("*", Token::Star),
+            ("=", Token::Equals),
+            ("<", Token::Less),
+            (">", Token::Greater),
+        ]
+        .into_iter()
+        .find(|(symbol, _)| rest.starts_with(symbol));
+        // a dot starts a number if a digit follows
+        let dot_number =
+            rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit());
+        if let Some((symbol, token)) = symbol {
+            tokens.push((token, start));
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+        } else if c == '.' && !dot_number {
+            tokens.push((Token::Dot, start));
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let end = rest[1..]
+                .find(c)
+                .ok_or_else(|| XPathError::Syntax(start, "unterminated literal".to_string()))?;
+            tokens.push((Token::Literal(rest[1..end + 1].to_string()), start));
+            while chars.next_if(|(i, _)| *i <= start + end + 1).is_some() {}
+        } else if c.is_ascii_digit() || dot_number {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..len]
+                .parse::<f64>()
+                .map_err(|_| XPathError::Syntax(start, "invalid number".to_string()))?;
+            tokens.push((Token::Number(number), start));
+            while chars.next_if(|(i, _)| *i < start + len).is_some() {}
+        } else if is_name_start(c) {
+            let name_len = |s: &str| s.find(|c| !is_name_char(c)).unwrap_or(s.len());
+            let len = name_len(rest);
+            let name = rest[..len].to_string();
+            let after = &rest[len..];
+            // a colon that isn't part of :: makes this a prefixed name
+            let token_len = if after.starts_with(':') && !after.starts_with("::") {
+                let local = &after[1..];
+                if local.starts_with('*') {
+                    tokens.push((Token::PrefixWildcard(name), start));
+                    len + 2
+                } else if local.starts_with(is_name_start) {
+                    let local_len = name_len(local);
+                    tokens.push((
+                        Token::PrefixedName(name, local[..local_len].to_string()),
+                        start,
+                    ));
+                    len + 1 + local_len
+                } else {
+                    return Err(XPathError::Syntax(start + len, "invalid name".to_string()));
+                }
+            } else {
+                tokens.push((Token::Name(name), start));
+                len
+            };
+            while chars.next_if(|(i, _)| *i < start + token_len).is_some() {}
+        } else {
+            return Err(XPathError::Syntax(
+                start,
+                format!("unexpected character {:?}", c),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end: usize,
+    resolve_prefix: &'a dyn Fn(&str) -> Option<String>,
+}
+
+// Parse an XPath expression that is a location path, or a union of location
+// paths with `|`. Prefixes in names are resolved with the given function.
+pub(crate) fn parse(
+    expr: &str,
+    resolve_prefix: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<Core>, XPathError> {
+    let mut parser = Parser {
+        tokens: tokenize(expr)?,
+        index: 0,
+        end: expr.len(),
+        resolve_prefix,
+    };
+    let mut paths = vec![parser.location_path()?];
+    while parser.eat(&Token::Pipe) {
+        paths.push(parser.location_path()?);
+    }
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected token"));
+    }
+    Ok(paths)
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn peek_second(&self) -> Option<&Token> {
+        self.tokens.get(self.index + 1).map(|(token, _)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(_, position)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn error(&self, message: &str) -> XPathError {
+        XPathError::Syntax(self.position(), message.to_string())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, message: &str) -> Result<(), XPathError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn at_step(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(
+                Token::Dot
+                    | Token::DoubleDot
+                    | Token::At
+                    | Token::Star
+                    | Token::Name(_)
+                    | Token::PrefixedName(_, _)
+                    | Token::PrefixWildcard(_)
+            )
+        )
+    }
+
+    fn location_path(&mut self) -> Result<Core, XPathError> {
+        let mut steps = Vec::new();
+        let core = if self.eat(&Token::Slash) {
+            if self.at_step() {
+                self.relative_location_path(&mut steps)?;
+            }
+            Core::Absolute
+        } else if self.eat(&Token::DoubleSlash) {
+            steps.push(descendant_or_self_step());
+            self.relative_location_path(&mut steps)?;
+            Core::Absolute
+        } else if self.at_step() {
+            self.relative_location_path(&mut steps)?;
+            Core::Relative
+        } else {
+            return Err(self.error("expected location path"));
+        };
+        Ok(core(LocationPath { steps }))
+    }
+
+    fn relative_location_path(&mut self, steps: &mut Vec<LocationStep>) -> Result<(), XPathError> {
+        loop {
+            steps.push(self.step()?);
+            if self.eat(&Token::DoubleSlash) {
+                steps.push(descendant_or_self_step());
+            } else if !self.eat(&Token::Slash) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<LocationStep, XPathError> {
+        if self.eat(&Token::Dot) {
+            return Ok(node_step(Axis::Self_));
+        }
+        if self.eat(&Token::DoubleDot) {
+            return Ok(node_step(Axis::Parent));
+        }
+        let axis = if self.eat(&Token::At) {
+            Axis::Attribute
+        } else if let (Some(Token::Name(name)), Some(Token::DoubleColon)) =
+            (self.peek(), self.peek_second())
+        {
+            let axis = axis(name).ok_or_else(|| self.error("unknown axis"))?;
+            self.index += 2;
+            axis
+        } else {
+            Axis::Child
+        };
+        let node_test = self.node_test()?;
+        let mut predicate = None;
+        while self.eat(&Token::LeftBracket) {
+            let expr = self.or_expr()?;
+            self.expect(&Token::RightBracket, "expected ]")?;
+            // multiple predicates must all hold
+            predicate = Some(match predicate {
+                Some(previous) => Pred::And(Box::new(previous), Box::new(expr)),
+                None => expr,
+            });
+        }
+        Ok(LocationStep {
+            axis,
+            node_test,
+            predicate,
+        })
+    }
+
+    fn node_test(&mut self) -> Result<NodeTest, XPathError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Star) => Ok(NodeTest::TagName {
+                namespace: None,
+                local_name: None,
+            }),
+            Some(Token::PrefixWildcard(prefix)) => Ok(NodeTest::TagName {
+                namespace: Some(self.namespace(&prefix)?),
+                local_name: None,
+            }),
+            Some(Token::PrefixedName(prefix, local_name)) => Ok(NodeTest::TagName {
+                namespace: Some(self.namespace(&prefix)?),
+                local_name: Some(local_name),
+            }),
+            Some(Token::Name(name)) if self.peek() == Some(&Token::LeftParen) => {
+                self.index += 1;
+                let node_test = match name.as_str() {
+                    "node" => NodeTest::Node,
+                    "text" => NodeTest::Text,
+                    "comment" => NodeTest::Comment,
+                    "processing-instruction" => match self.peek() {
+                        Some(Token::Literal(target)) => {
+                            let target = target.clone();
+                            self.index += 1;
+                            NodeTest::ProcessingInstruction(Some(target))
+                        }
+                        _ => NodeTest::ProcessingInstruction(None),
+                    },
+                    _ => {
+                        return Err(XPathError::Syntax(
+                            position,
+                            format!("{}() is not a node test", name),
+                        ))
+                    }
+                };
+                self.expect(&Token::RightParen, "expected )")?;
+                Ok(node_test)
+            }
+            Some(Token::Name(name)) => Ok(NodeTest::TagName {
+                // an unprefixed name is in no namespace
+                namespace: Some(String::new()),
+                local_name: Some(name),
+            }),
+            _ => Err(XPathError::Syntax(
+                position,
+                "expected node test".to_string(),
+            )),
+        }
+    }
+
+    fn namespace(&self, prefix: &str) -> Result<String, XPathError> {
+        (self.resolve_prefix)(prefix)
+            .ok_or_else(|| XPathError::UndeclaredPrefix(prefix.to_string()))
+    }
+
+    fn or_expr(&mut self) -> Result<Pred, XPathError> {
+        let mut left = self.and_expr()?;
+        while self.peek() == Some(&Token::Name("or".to_string())) {
+            self.index += 1;
+            left = Pred::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Pred, XPathError> {
+        let mut left = self.comparison_expr()?;
+        while self.peek() == Some(&Token::Name("and".to_string())) {
+            self.index += 1;
+            left = Pred::And(Box::new(left), Box::new(self.comparison_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison_expr(&mut self) -> Result<Pred, XPathError> {
+        // a literal can be on either side of a comparison with a path
+        if let Some(Token::Literal(value)) = self.peek() {
+            let value = value.clone();
+            self.index += 1;
+            let Some(equals) = self.comparison_operator()? else {
+                return Err(XPathError::Unsupported(
+                    "literals outside of comparisons".to_string(),
+                ));
+            };
+            let Pred::Core(core) = self.primary_expr()? else {
+                return Err(XPathError::Unsupported(
+                    "comparisons with boolean expressions".to_string(),
+                ));
+            };
+            return Ok(comparison(core, equals, value));
+        }
+        let operand = self.primary_expr()?;
+        let Some(equals) = self.comparison_operator()? else {
+            return Ok(operand);
+        };
+        let Pred::Core(core) = operand else {
+            return Err(XPathError::Unsupported(
+                "comparisons with boolean expressions".to_string(),
+            ));
+        };
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(comparison(core, equals, value)),
+            Some(Token::Number(_)) => {
+                Err(XPathError::Unsupported("numeric comparisons".to_string()))
+            }
+            _ => Err(XPathError::Unsupported(
+                "comparisons with anything but a literal".to_string(),
+            )),
+        }
+    }
+
+    // whether the next token is `=` rather than `!=`, if it is a comparison
+    fn comparison_operator(&mut self) -> Result<Option<bool>, XPathError> {
+        let equals = match self.peek() {
+            Some(Token::Equals) => true,
+            Some(Token::NotEquals) => false,
+            Some(Token::Less | Token::LessEqual | Token::Greater | Token::GreaterEqual) => {
+                return Err(XPathError::Unsupported(
+                    "relational comparisons".to_string(),
+                ))
+            }
+            _ => return Ok(None),
+        };
+        self.index += 1;
+        Ok(Some(equals))
+    }
+
+    fn primary_expr(&mut self) -> Result<Pred, XPathError> {
+        match (self.peek(), self.peek_second()) {
+            (Some(Token::LeftParen), _) => {
+                self.index += 1;
+                let expr = self.or_expr()?;
+                self.expect(&Token::RightParen, "expected )")?;
+                Ok(expr)
+            }
+            (Some(Token::Name(name)), Some(Token::LeftParen)) if name == "not" => {
+                self.index += 2;
+                let expr = self.or_expr()?;
+                self.expect(&Token::RightParen, "expected )")?;
+                Ok(Pred::Not(Box::new(expr)))
+            }
+            (Some(Token::Name(name)), Some(Token::LeftParen))
+                if !matches!(
+                    name.as_str(),
+                    "node" | "text" | "comment" | "processing-instruction"
+                ) =>
+            {
+                Err(XPathError::Unsupported(format!("function {}()", name)))
+            }
+            (Some(Token::Number(_)), _) => {
+                Err(XPathError::Unsupported("positional predicates".to_string()))
+            }
+            (Some(Token::Literal(_)), _) => Err(XPathError::Unsupported(
+                "comparisons between literals".to_string(),
+            )),
+            _ => Ok(Pred::Core(self.location_path()?)),
+        }
+    }
+}
+
+// a path compared to a string: true if the string value of any node it
+// selects is (or is not) equal to it
+fn comparison(core: Core, equals: bool, value: String) -> Pred {
+    if equals {
+        Pred::Equals(core, value)
+    } else {
+        Pred::NotEquals(core, value)
+    }
+}
+
+fn axis(name: &str) -> Option<Axis> {
+    Some(match name {
+        "ancestor" => Axis::Ancestor,
+        "ancestor-or-self" => Axis::AncestorOrSelf,
+        "attribute" => Axis::Attribute,
+        "child" => Axis::Child,
+        "descendant" => Axis::Descendant,
+        "descendant-or-self" => Axis::DescendantOrSelf,
+        "following" => Axis::Following,
+        "following-sibling" => Axis::FollowingSibling,
+        "namespace" => Axis::Namespace,
+        "parent" => Axis::Parent,
+        "preceding" => Axis::Preceding,
+        "preceding-sibling" => Axis::PrecedingSibling,
+        "self" => Axis::Self_,
+        _ => return None,
+    })
+}
+
+fn node_step(axis: Axis) -> LocationStep {
+    LocationStep {
+        axis,
+        node_test: NodeTest::Node,
+        predicate: None,
+    }
+}
+
+fn descendant_or_self_step() -> LocationStep {
+    node_step(Axis::DescendantOrSelf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(prefix: &str) -> Option<String> {
+        (prefix == "ex").then(|| "http://example.com".to_string())
+    }
+
+    fn parse_one(expr: &str) -> Core {
+        let mut paths = parse(expr, &resolve).unwrap();
+        assert_eq!(paths.len(), 1);
+        paths.pop().unwrap()
+    }
+
+    fn step(axis: Axis, namespace: &str, local_name: &str) -> LocationStep {
+        LocationStep {
+            axis,
+            node_test: NodeTest::TagName {
+                namespace: Some(namespace.to_string()),
+                local_name: Some(local_name.to_string()),
+            },
+            predicate: None,
+        }
+    }
+
+    #[test]
+    fn test_absolute_abbreviated() {
+        assert_eq!(
+            parse_one("//chapter/title"),
+            Core::Absolute(LocationPath {
+                steps: vec![
+                    descendant_or_self_step(),
+                    step(Axis::Child, "", "chapter"),
+                    step(Axis::Child, "", "title"),
+                ]
+            })
+        );
+        assert_eq!(
+            parse_one("/"),
+            Core::Absolute(LocationPath { steps: vec![] })
+        );
+    }
+
+    #[test]
+    fn test_unabbreviated() {
+        assert_eq!(
+            parse_one("child::doc/descendant::ex:p/following-sibling::q"),
+            Core::Relative(LocationPath {
+                steps: vec![
+                    step(Axis::Child, "", "doc"),
+                    step(Axis::Descendant, "http://example.com", "p"),
+                    step(Axis::FollowingSibling, "", "q"),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_abbreviated_steps() {
+        assert_eq!(
+            parse_one("../@id"),
+            Core::Relative(LocationPath {
+                steps: vec![node_step(Axis::Parent), step(Axis::Attribute, "", "id")]
+            })
+        );
+        assert_eq!(
+            parse_one(".//a"),
+            Core::Relative(LocationPath {
+                steps: vec![
+                    node_step(Axis::Self_),
+                    descendant_or_self_step(),
+                    step(Axis::Child, "", "a")
+                ]
+            })
+        );
+        assert_eq!(
+            parse_one("//@id"),
+            Core::Absolute(LocationPath {
+                steps: vec![descendant_or_self_step(), step(Axis::Attribute, "", "id")]
+            })
+        );
+    }
+
+    #[test]
+    fn test_node_tests() {
+        let Core::Relative(path) =
+            parse_one("* / ex:* / text() / comment() / node() / processing-instruction('x')")
+        else {
+            panic!("expected relative path");
+        };
+        let node_tests = path
+            .steps
+            .into_iter()
+            .map(|step| step.node_test)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            node_tests,
+            vec![
+                NodeTest::TagName {
+                    namespace: None,
+                    local_name: None
+                },
+                NodeTest::TagName {
+                    namespace: Some("http://example.com".to_string()),
+                    local_name: None
+                },
+                NodeTest::Text,
+                NodeTest::Comment,
+                NodeTest::Node,
+                NodeTest::ProcessingInstruction(Some("x".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_predicates() {
+        let Core::Relative(path) = parse_one("a[b and not(c or ex:d)][/e]") else {
+            panic!("expected relative path");
+        };
+        let relative = |name: &str| {
+            Pred::Core(Core::Relative(LocationPath {
+                steps: vec![step(Axis::Child, "", name)],
+            }))
+        };
+        assert_eq!(
+            path.steps[0].predicate,
+            Some(Pred::And(
+                Box::new(Pred::And(
+                    Box::new(relative("b")),
+                    Box::new(Pred::Not(Box::new(Pred::Or(
+                        Box::new(relative("c")),
+                        Box::new(Pred::Core(Core::Relative(LocationPath {
+                            steps: vec![step(Axis::Child, "http://example.com", "d")]
+                        })))
+                    ))))
+                )),
+                Box::new(Pred::Core(Core::Absolute(LocationPath {
+                    steps: vec![step(Axis::Child, "", "e")]
+                })))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let predicate = |expr: &str| {
+            let Core::Relative(mut path) = parse_one(expr) else {
+                panic!("expected relative path");
+            };
+            path.steps.pop().unwrap().predicate.unwrap()
+        };
+        let relative = |steps| Core::Relative(LocationPath { steps });
+        assert_eq!(
+            predicate("a[@type='x']"),
+            Pred::Equals(
+                relative(vec![step(Axis::Attribute, "", "type")]),
+                "x".to_string()
+            )
+        );
+        assert_eq!(
+            predicate("a[\"foo\" = .]"),
+            Pred::Equals(relative(vec![node_step(Axis::Self_)]), "foo".to_string())
+        );
+        assert_eq!(
+            predicate("a[b/c != '']"),
+            Pred::NotEquals(
+                relative(vec![step(Axis::Child, "", "b"), step(Axis::Child, "", "c")]),
+                "".to_string()
+            )
+        );
+        assert_eq!(
+            parse("a[b < 'x']", &resolve),
+            Err(XPathError::Unsupported(
+                "relational comparisons".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("a[b = 1]", &resolve),
+            Err(XPathError::Unsupported("numeric comparisons".to_string()))
+        );
+        assert_eq!(
+            parse("a['x']", &resolve),
+            Err(XPathError::Unsupported(
+                "literals outside of comparisons".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_union() {
+        assert_eq!(parse("a | //b", &resolve).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("a/", &resolve),
+            Err(XPathError::Syntax(2, "expected node test".to_string()))
+        );
+        assert_eq!(
+            parse("a[b", &resolve),
+            Err(XPathError::Syntax(3, "expected ]".to_string()))
+        );
+        assert_eq!(
+            parse("bogus::a", &resolve),
+            Err(XPathError::Syntax(0, "unknown axis".to_string()))
+        );
+        assert_eq!(
+            parse("no:a", &resolve),
+            Err(XPathError::UndeclaredPrefix("no".to_string()))
+        );
+        assert_eq!(
+            parse("a[1]", &resolve),
+            Err(XPathError::Unsupported("positional predicates".to_string()))
+        );
+        assert_eq!(
+            parse("a[count(b)]", &resolve),
+            Err(XPathError::Unsupported("function count()".to_string()))
+        );
+        assert_eq!(
+            parse("a b", &resolve),
+            Err(XPathError::Syntax(2, "unexpected token".to_string()))
+        );
+        assert_eq!(
+            parse("a['x", &resolve),
+            Err(XPathError::Syntax(2, "unterminated literal".to_string()))
+        );
+    }
+}
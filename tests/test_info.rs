@@ -1,4 +1,4 @@
-use xoz::{NodeType, Xoz};
+use xoz::{NodeId, NodeSet, NodeType, Xoz};
 
 #[test]
 fn test_preorder() {
@@ -45,3 +45,104 @@ fn test_subtree_tags_deeper() {
         2
     );
 }
+
+#[test]
+fn test_node_at_preorder_and_postorder() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<a x="X"><b><c/>text</b><!--d--><e y="Y"/></a>"#)
+        .unwrap();
+    let mut nodes = vec![root];
+    nodes.extend(xoz.descendants(root));
+    let mut attributes = Vec::new();
+    for node in &nodes {
+        attributes.extend(xoz.attributes(*node));
+    }
+    nodes.extend(attributes);
+
+    for node in &nodes {
+        assert_eq!(xoz.node_at_preorder(root, xoz.preorder(*node)), Some(*node));
+    }
+    // the preorder numbers not in use are those of the hidden attributes
+    // nodes
+    let found = (0..100)
+        .filter_map(|preorder| xoz.node_at_preorder(root, preorder))
+        .count();
+    assert_eq!(found, nodes.len());
+
+    // postorder sorts descendants before their ancestors, and otherwise
+    // in document order
+    for a in &nodes {
+        for b in &nodes {
+            let expected = if xoz.is_ancestor(*a, *b) {
+                std::cmp::Ordering::Greater
+            } else if xoz.is_ancestor(*b, *a) {
+                std::cmp::Ordering::Less
+            } else {
+                xoz.preorder(*a).cmp(&xoz.preorder(*b))
+            };
+            assert_eq!(xoz.postorder(*a).cmp(&xoz.postorder(*b)), expected);
+        }
+    }
+}
+
+#[test]
+fn test_node_id() {
+    let mut xoz = Xoz::new();
+    let root1 = xoz.parse_str("<a><b/></a>").unwrap();
+    let root2 = xoz.parse_str(r#"<a x="X"><b/></a>"#).unwrap();
+    let a2 = xoz.document_element(root2);
+    let x2 = xoz.attribute_node(a2, "x").unwrap();
+    for node in [root1, xoz.document_element(root1), root2, a2, x2] {
+        let node_id = xoz.node_id(node);
+        assert_eq!(xoz.node_from_id(node_id), Some(node));
+        assert_eq!(
+            xoz.node_from_id(NodeId::from_bytes(node_id.to_bytes())),
+            Some(node)
+        );
+    }
+    assert_ne!(xoz.node_id(root1), xoz.node_id(root2));
+    // a closing position isn't a node
+    assert_eq!(xoz.node_from_id(NodeId::new(0, 3)), None);
+    assert_eq!(xoz.node_from_id(NodeId::new(0, 100)), None);
+    assert_eq!(xoz.node_from_id(NodeId::new(2, 0)), None);
+}
+
+#[test]
+fn test_node_set_document_order() {
+    let mut xoz = Xoz::new();
+    let root1 = xoz.parse_str("<a><b/><c/></a>").unwrap();
+    let root2 = xoz.parse_str("<d/>").unwrap();
+    let a = xoz.document_element(root1);
+    let b = xoz.first_child(a).unwrap();
+    let c = xoz.next_sibling(b).unwrap();
+    let d = xoz.document_element(root2);
+
+    let set: NodeSet = [d, c, root1, b, c].into_iter().collect();
+    assert_eq!(set.len(), 4);
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![root1, b, c, d]);
+    let mut keys = set.iter().map(|n| xoz.sort_key(n)).collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(
+        keys,
+        set.iter().map(|n| xoz.sort_key(n)).collect::<Vec<_>>()
+    );
+
+    let mut other = NodeSet::new();
+    assert!(other.insert(a));
+    assert!(other.insert(d));
+    assert!(!other.insert(a));
+    assert_eq!(
+        set.union(&other).iter().collect::<Vec<_>>(),
+        vec![root1, a, b, c, d]
+    );
+    assert_eq!(set.intersection(&other).iter().collect::<Vec<_>>(), vec![d]);
+    assert_eq!(
+        set.difference(&other).iter().collect::<Vec<_>>(),
+        vec![root1, b, c]
+    );
+    assert_eq!(set.first(), Some(root1));
+    assert_eq!(set.last(), Some(d));
+    assert!(set.contains(b));
+    assert!(!set.contains(a));
+}
@@ -1,4 +1,4 @@
-use xoz::{NodeType, Xoz};
+use xoz::{Node, NodeType, VisitControl, Visitor, Xoz};
 
 #[test]
 fn test_following_siblings() {
@@ -399,6 +399,71 @@ fn test_typed_following2() {
     assert_eq!(following, vec![f2, f3]);
 }
 
+#[test]
+fn test_typed_children() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc row="attr"><row/>text<x><row/><row/></x><!--c--><row><row/></row><y/></doc>"#,
+        )
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let row = NodeType::element("row");
+
+    let expected: Vec<_> = xoz
+        .children(doc_el)
+        .filter(|n| xoz.node_type(*n) == &row)
+        .collect();
+    assert_eq!(expected.len(), 2);
+    let children: Vec<_> = xoz.typed_children(doc_el, row.clone()).collect();
+    assert_eq!(children, expected);
+
+    let x = xoz
+        .typed_children(doc_el, NodeType::element("x"))
+        .next()
+        .unwrap();
+    assert_eq!(xoz.typed_children(x, row.clone()).count(), 2);
+    assert_eq!(
+        xoz.typed_children(doc_el, NodeType::element("missing"))
+            .count(),
+        0
+    );
+    // attributes aren't children
+    assert_eq!(
+        xoz.typed_children(doc_el, NodeType::attribute("row"))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_typed_following_siblings() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><a/><b><a/></b><a/>text<a/></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let a1 = xoz.first_child(doc_el).unwrap();
+    let b = xoz.next_sibling(a1).unwrap();
+    let nested = xoz.first_child(b).unwrap();
+    let a = NodeType::element("a");
+
+    let expected: Vec<_> = xoz
+        .following_siblings(a1)
+        .filter(|n| xoz.node_type(*n) == &a)
+        .collect();
+    assert_eq!(expected.len(), 2);
+    let siblings: Vec<_> = xoz.typed_following_siblings(a1, a.clone()).collect();
+    assert_eq!(siblings, expected);
+    assert_eq!(
+        xoz.typed_following_siblings(b, a.clone())
+            .collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(xoz.typed_following_siblings(nested, a.clone()).count(), 0);
+    assert_eq!(xoz.typed_following_siblings(root, a).count(), 0);
+}
+
 #[test]
 fn test_attributes_axis() {
     let mut xoz = Xoz::new();
@@ -446,3 +511,231 @@ fn test_self_axis() {
     let nodes: Vec<_> = xoz.axis_self(a).collect();
     assert_eq!(nodes, vec![a]);
 }
+
+#[test]
+fn test_typed_descendants_any() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><a><b/><a><c/></a></a>text<b a="A"/><c><b/></c></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let node_types = [
+        NodeType::element("a"),
+        NodeType::element("b"),
+        NodeType::element("missing"),
+        NodeType::element("a"),
+    ];
+    for node in xoz.descendants(root).collect::<Vec<_>>() {
+        let expected: Vec<_> = xoz
+            .descendants(node)
+            .filter(|n| node_types.contains(xoz.node_type(*n)))
+            .collect();
+        let found: Vec<_> = xoz.typed_descendants_any(node, &node_types).collect();
+        assert_eq!(found, expected);
+    }
+    assert_eq!(xoz.typed_descendants_any(doc_el, &[]).count(), 0);
+}
+
+#[test]
+fn test_typed_descendants_name_wildcards() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(concat!(
+            r#"<doc xmlns:x="http://example.com/x" xmlns:y="http://example.com/y">"#,
+            r#"<x:a x:a="attr"/><y:a><x:b/></y:a><a/></doc>"#
+        ))
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let xa = xoz.first_child(doc_el).unwrap();
+    let ya = xoz.next_sibling(xa).unwrap();
+    let xb = xoz.first_child(ya).unwrap();
+    let a = xoz.next_sibling(ya).unwrap();
+
+    assert_eq!(
+        xoz.typed_descendants_namespace(root, "http://example.com/x")
+            .collect::<Vec<_>>(),
+        vec![xa, xb]
+    );
+    assert_eq!(
+        xoz.typed_descendants_namespace(ya, "http://example.com/x")
+            .collect::<Vec<_>>(),
+        vec![xb]
+    );
+    assert_eq!(
+        xoz.typed_descendants_namespace(root, "")
+            .collect::<Vec<_>>(),
+        vec![doc_el, a]
+    );
+    // attributes aren't included
+    assert_eq!(
+        xoz.typed_descendants_local_name(root, "a")
+            .collect::<Vec<_>>(),
+        vec![xa, ya, a]
+    );
+    assert_eq!(xoz.typed_descendants_local_name(root, "missing").count(), 0);
+}
+
+#[test]
+fn test_descendants_reverse() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<a x="1"><b y="2"><c/>text</b><d><e z="3"/></d></a>"#)
+        .unwrap();
+    let a = xoz.document_element(root);
+    let forward = xoz.descendants(a).collect::<Vec<_>>();
+    assert_eq!(forward.len(), 5);
+    let mut reverse = xoz.descendants(a).rev().collect::<Vec<_>>();
+    reverse.reverse();
+    assert_eq!(reverse, forward);
+
+    // from both ends at once, each node is given once
+    let mut iter = xoz.descendants(a);
+    let mut front = Vec::new();
+    let mut back = Vec::new();
+    while let Some(n) = iter.next() {
+        front.push(n);
+        match iter.next_back() {
+            Some(n) => back.push(n),
+            None => break,
+        }
+    }
+    back.reverse();
+    front.extend(back);
+    assert_eq!(front, forward);
+
+    // going back only after going forward for a while
+    let mut iter = xoz.descendants(a);
+    assert_eq!(iter.next(), Some(forward[0]));
+    assert_eq!(iter.next(), Some(forward[1]));
+    let rest = iter.rev().collect::<Vec<_>>();
+    assert_eq!(rest, forward[2..].iter().rev().copied().collect::<Vec<_>>());
+
+    // once done going forward, there's nothing left going back
+    let mut iter = xoz.descendants(a);
+    iter.by_ref().for_each(drop);
+    assert_eq!(iter.next_back(), None);
+
+    let b = xoz.first_child(a).unwrap();
+    let c = xoz.first_child(b).unwrap();
+    assert_eq!(xoz.descendants(c).next_back(), None);
+}
+
+#[test]
+fn test_level_order() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<a x="1"><b><d/><e/></b><c><f/></c></a>"#)
+        .unwrap();
+    let a = xoz.document_element(root);
+    let names = xoz
+        .level_order(root)
+        .filter_map(|n| xoz.node_name(n))
+        .map(|name| String::from_utf8(name.local_name().to_vec()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a", "b", "c", "d", "e", "f"]);
+    assert_eq!(xoz.level_order(root).next(), Some(root));
+    assert_eq!(xoz.level_order(a).count(), 6);
+}
+
+struct Recorder {
+    events: Vec<(&'static str, String)>,
+    skip: &'static str,
+    stop: &'static str,
+}
+
+impl Recorder {
+    fn new(skip: &'static str, stop: &'static str) -> Self {
+        Self {
+            events: Vec::new(),
+            skip,
+            stop,
+        }
+    }
+
+    fn name(xoz: &Xoz, node: Node) -> String {
+        xoz.node_name(node)
+            .map(|name| String::from_utf8(name.local_name().to_vec()).unwrap())
+            .unwrap_or_default()
+    }
+}
+
+impl Visitor for Recorder {
+    fn enter(&mut self, xoz: &Xoz, node: Node) -> VisitControl {
+        let name = Self::name(xoz, node);
+        self.events.push(("enter", name.clone()));
+        if name == self.skip {
+            VisitControl::SkipChildren
+        } else if name == self.stop {
+            VisitControl::Stop
+        } else {
+            VisitControl::Continue
+        }
+    }
+
+    fn leave(&mut self, xoz: &Xoz, node: Node) -> VisitControl {
+        self.events.push(("leave", Self::name(xoz, node)));
+        VisitControl::Continue
+    }
+}
+
+#[test]
+fn test_visit() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<a><b><c/></b><d/></a>").unwrap();
+    let a = xoz.document_element(root);
+
+    let mut recorder = Recorder::new("", "");
+    xoz.visit(a, &mut recorder);
+    assert_eq!(
+        recorder.events,
+        vec![
+            ("enter", "a".to_string()),
+            ("enter", "b".to_string()),
+            ("enter", "c".to_string()),
+            ("leave", "c".to_string()),
+            ("leave", "b".to_string()),
+            ("enter", "d".to_string()),
+            ("leave", "d".to_string()),
+            ("leave", "a".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_visit_skip_children() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<a><b><c/></b><d/></a>").unwrap();
+    let a = xoz.document_element(root);
+
+    let mut recorder = Recorder::new("b", "");
+    xoz.visit(a, &mut recorder);
+    assert_eq!(
+        recorder.events,
+        vec![
+            ("enter", "a".to_string()),
+            ("enter", "b".to_string()),
+            ("leave", "b".to_string()),
+            ("enter", "d".to_string()),
+            ("leave", "d".to_string()),
+            ("leave", "a".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_visit_stop() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<a><b><c/></b><d/></a>").unwrap();
+    let a = xoz.document_element(root);
+
+    let mut recorder = Recorder::new("", "c");
+    xoz.visit(a, &mut recorder);
+    assert_eq!(
+        recorder.events,
+        vec![
+            ("enter", "a".to_string()),
+            ("enter", "b".to_string()),
+            ("enter", "c".to_string()),
+        ]
+    );
+}
@@ -230,3 +230,164 @@ fn test_typed_foll2() {
     let found = xoz.typed_foll(f1, NodeType::element("f"));
     assert_eq!(found, Some(f2));
 }
+
+#[test]
+fn test_typed_reverse_jumps_match_axes() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(concat!(
+            r#"<a x="1"><b/><a><b><a x="2"/></b>text<c/></a>"#,
+            r#"<c><b/><a><c/><b/></a></c><!--c--><b/></a>"#
+        ))
+        .unwrap();
+    let nodes = xoz.descendants(root).collect::<Vec<_>>();
+    for name in ["a", "b", "c"] {
+        let node_type = NodeType::element(name);
+        for node in &nodes {
+            let node = *node;
+            let has_type = |n: &xoz::Node| xoz.node_type(*n) == &node_type;
+            assert_eq!(
+                xoz.typed_preceding(node, node_type.clone()),
+                xoz.axis_preceding(node).filter(has_type).last(),
+            );
+            assert_eq!(
+                xoz.typed_preceding_sibling(node, node_type.clone()),
+                xoz.preceding_siblings(node).find(has_type),
+            );
+            assert_eq!(
+                xoz.typed_ancestor(node, node_type.clone()),
+                xoz.ancestors(node).find(has_type),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_typed_ancestor_of_attribute() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(r#"<a><b c="C"/></a>"#).unwrap();
+    let a = xoz.document_element(root);
+    let b = xoz.first_child(a).unwrap();
+    let c = xoz.attribute_node(b, "c").unwrap();
+    assert_eq!(xoz.typed_ancestor(c, NodeType::element("b")), Some(b));
+    assert_eq!(xoz.typed_ancestor(c, NodeType::element("a")), Some(a));
+    assert_eq!(xoz.typed_ancestor(c, NodeType::Document), Some(root));
+}
+
+#[test]
+fn test_depth_and_level_ancestor_match_ancestors() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<a xmlns:x="http://example.com" x:y="Y"><b c="C"><d>text</d></b><!--e--><f/></a>"#,
+        )
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let mut nodes = xoz.descendants(root).collect::<Vec<_>>();
+    nodes.push(root);
+    for node in xoz.descendants(root).collect::<Vec<_>>() {
+        nodes.extend(xoz.attributes(node));
+    }
+    let namespaces = xoz.namespaces_child(doc_el).unwrap();
+    nodes.push(xoz.first_child(namespaces).unwrap());
+
+    for node in &nodes {
+        let ancestors = xoz.ancestors_or_self(*node).collect::<Vec<_>>();
+        assert_eq!(xoz.depth(*node), ancestors.len() - 1);
+        for (levels, ancestor) in ancestors.iter().enumerate() {
+            assert_eq!(xoz.level_ancestor(*node, levels), Some(*ancestor));
+        }
+        assert_eq!(xoz.level_ancestor(*node, ancestors.len()), None);
+    }
+
+    for a in &nodes {
+        for b in &nodes {
+            let expected = xoz
+                .ancestors_or_self(*a)
+                .find(|ancestor| xoz.is_ancestor_or_self(*ancestor, *b))
+                .unwrap();
+            assert_eq!(xoz.lowest_common_ancestor(*a, *b), Some(expected));
+        }
+    }
+}
+
+#[test]
+fn test_lowest_common_ancestor_different_documents() {
+    let mut xoz = Xoz::new();
+    let root1 = xoz.parse_str("<a/>").unwrap();
+    let root2 = xoz.parse_str("<a/>").unwrap();
+    assert_eq!(xoz.lowest_common_ancestor(root1, root2), None);
+}
+
+#[test]
+fn test_child_count_and_nth_child() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(concat!(
+            r#"<doc xmlns:x="http://example.com" x:a="A" b="B">"#,
+            r#"<a><a/><a>text</a></a>text<!--c--><b c="C"/><?pi?><c><d/></c></doc>"#
+        ))
+        .unwrap();
+    let mut nodes = xoz.descendants(root).collect::<Vec<_>>();
+    nodes.push(root);
+    for node in nodes {
+        let children = xoz.children(node).collect::<Vec<_>>();
+        assert_eq!(xoz.child_count(node), children.len());
+        for (i, child) in children.iter().enumerate() {
+            assert_eq!(xoz.nth_child(node, i), Some(*child));
+            assert_eq!(xoz.child_index(node, *child), Some(i));
+        }
+        assert_eq!(xoz.nth_child(node, children.len()), None);
+    }
+}
+
+#[test]
+fn test_child_index_of_attribute() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(r#"<doc a="A"><b/></doc>"#).unwrap();
+    let doc_el = xoz.document_element(root);
+    let a = xoz.attribute_node(doc_el, "a").unwrap();
+    let b = xoz.first_child(doc_el).unwrap();
+    assert_eq!(xoz.child_index(doc_el, a), None);
+    assert_eq!(xoz.child_index(doc_el, b), Some(0));
+    assert_eq!(xoz.child_index(root, b), None);
+}
+
+#[test]
+fn test_child_index_heap_size() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<doc><a/><b/><c/></doc>").unwrap();
+    let doc_el = xoz.document_element(root);
+    let before = xoz.heap_size();
+    // the child index table is only built when it's first needed
+    assert_eq!(xoz.child_count(doc_el), 3);
+    let after = xoz.heap_size();
+    assert!(after > before);
+    assert_eq!(
+        xoz.nth_child(doc_el, 1).map(|b| xoz.child_index(doc_el, b)),
+        Some(Some(1))
+    );
+    assert_eq!(xoz.heap_size(), after);
+}
+
+#[test]
+fn test_nth_child_many() {
+    let mut xml = String::from(r#"<doc a="A">"#);
+    for i in 0..1000 {
+        if i % 3 == 0 {
+            xml.push_str("<row><cell/><cell/></row>");
+        } else {
+            xml.push_str("<row/>");
+        }
+    }
+    xml.push_str("</doc>");
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(&xml).unwrap();
+    let doc_el = xoz.document_element(root);
+    assert_eq!(xoz.child_count(doc_el), 1000);
+    let children = xoz.children(doc_el).collect::<Vec<_>>();
+    for i in [0, 1, 2, 3, 499, 500, 998, 999] {
+        assert_eq!(xoz.nth_child(doc_el, i), Some(children[i]));
+        assert_eq!(xoz.child_index(doc_el, children[i]), Some(i));
+    }
+}
@@ -0,0 +1,90 @@
+use xoz::{Node, NodeType, Xoz};
+
+#[test]
+fn test_node_ref_navigation() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><chapter n="1"><p>a</p><p>b</p></chapter><chapter n="2"/></doc>"#)
+        .unwrap();
+    let root = xoz.node_ref(root);
+    let doc = root.document_element();
+    let chapter1 = doc.first_child().unwrap();
+    let chapter2 = chapter1.next_sibling().unwrap();
+
+    assert_eq!(chapter1.attribute_value("n"), Some("1"));
+    assert_eq!(chapter2.previous_sibling(), Some(chapter1));
+    assert_eq!(chapter1.parent(), Some(doc));
+    assert_eq!(chapter1.child_count(), 2);
+    assert_eq!(
+        chapter1
+            .children()
+            .map(|p| p.string_value())
+            .collect::<Vec<_>>(),
+        vec!["a", "b"]
+    );
+    assert_eq!(
+        root.typed_descendants(NodeType::element("chapter"))
+            .collect::<Vec<_>>(),
+        vec![chapter1, chapter2]
+    );
+    assert!(doc.is_ancestor_of(chapter2));
+    let n: Node = chapter2.into();
+    assert_eq!(xoz.attribute_value(n, "n"), Some("2"));
+    assert_eq!(chapter2, n);
+}
+
+#[test]
+fn test_node_ref_display() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<doc><a>A</a><b/></doc>").unwrap();
+    let root = xoz.node_ref(root);
+    let a = root.document_element().first_child().unwrap();
+    assert_eq!(a.to_string(), "<a>A</a>");
+    assert_eq!(a.next_sibling().unwrap().to_string(), "<b/>");
+    assert_eq!(root.to_string(), "<doc><a>A</a><b/></doc>");
+}
+
+#[test]
+fn test_node_ref_debug() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc xmlns:ex="http://example.com"><ex:a ex:id="x">A</ex:a></doc>"#)
+        .unwrap();
+    let root = xoz.node_ref(root);
+    let a = root.document_element().first_child().unwrap();
+    let text = a.first_child().unwrap();
+    let id = a.attributes().next().unwrap();
+    assert_eq!(
+        format!("{:?}", text),
+        r#"NodeRef { name: "text()", path: "/doc[1]/ex:a[1]/text()[1]" }"#
+    );
+    assert_eq!(
+        format!("{:?}", id),
+        r#"NodeRef { name: "ex:id", path: "/doc[1]/ex:a[1]/@ex:id" }"#
+    );
+    assert_eq!(
+        format!("{:?}", a),
+        r#"NodeRef { name: "ex:a", path: "/doc[1]/ex:a[1]" }"#
+    );
+    assert!(format!("{:?}", root).contains(r#"path: "/""#));
+}
+
+#[test]
+fn test_node_ref_display_inherited_namespace() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc xmlns:x="urn:x" xmlns="urn:d"><a><b x:k="v" xml:lang="en"/></a></doc>"#)
+        .unwrap();
+    let root = xoz.node_ref(root);
+    let a = root.document_element().first_child().unwrap();
+    // the namespaces declared on ancestors are declared on the node
+    assert_eq!(
+        a.to_string(),
+        r#"<a xmlns:x="urn:x" xmlns="urn:d"><b x:k="v" xml:lang="en"/></a>"#
+    );
+    let b = a.first_child().unwrap();
+    assert_eq!(
+        b.to_string(),
+        r#"<b xmlns:x="urn:x" xmlns="urn:d" x:k="v" xml:lang="en"/>"#
+    );
+}
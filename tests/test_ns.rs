@@ -1,4 +1,4 @@
-use xoz::{NodeName, Xoz};
+use xoz::{error::QNameError, NodeName, Xoz};
 
 #[test]
 fn test_simple_empty_prefix_for_namespace() {
@@ -252,3 +252,145 @@ fn test_node_full_name_attribute_explicit() {
     let full_name = xoz.node_full_name(a).unwrap();
     assert_eq!(full_name, "ns:a");
 }
+
+#[test]
+fn test_namespace_for_prefix_nested() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns="http://default.com" xmlns:ex="http://example.com"><a xmlns:ex="http://other.com"><b xmlns="">text</b></a></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let a = xoz.first_child(doc).unwrap();
+    let b = xoz.first_child(a).unwrap();
+    let text = xoz.first_child(b).unwrap();
+
+    assert_eq!(xoz.namespace_for_prefix(doc, b"ex"), Some(&b"http://example.com"[..]));
+    assert_eq!(xoz.namespace_for_prefix(a, b"ex"), Some(&b"http://other.com"[..]));
+    assert_eq!(xoz.namespace_for_prefix(text, b"ex"), Some(&b"http://other.com"[..]));
+    assert_eq!(xoz.namespace_for_prefix(a, b""), Some(&b"http://default.com"[..]));
+    assert_eq!(xoz.namespace_for_prefix(b, b""), Some(&b""[..]));
+    assert_eq!(
+        xoz.namespace_for_prefix(b, b"xml"),
+        Some(&b"http://www.w3.org/XML/1998/namespace"[..])
+    );
+    assert!(xoz.is_prefix_defined(b, b"ex"));
+    assert!(!xoz.is_prefix_defined(b, b"missing"));
+}
+
+#[test]
+fn test_namespaces_in_scope() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns="http://default.com" xmlns:ex="http://example.com" ex:id="1"><b xmlns="" xmlns:y="http://y.com"/></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let b = xoz.first_child(doc).unwrap();
+    let id = xoz
+        .attribute_node(doc, NodeName::new("http://example.com", "id"))
+        .unwrap();
+
+    assert_eq!(
+        xoz.namespaces_in_scope(b).collect::<Vec<_>>(),
+        vec![
+            (&b"y"[..], &b"http://y.com"[..]),
+            (&b"ex"[..], &b"http://example.com"[..]),
+            (&b"xml"[..], &b"http://www.w3.org/XML/1998/namespace"[..]),
+        ]
+    );
+    assert_eq!(
+        xoz.namespaces_in_scope(id).collect::<Vec<_>>(),
+        xoz.namespaces_in_scope(doc).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        xoz.namespaces_in_scope(root).collect::<Vec<_>>(),
+        vec![(&b"xml"[..], &b"http://www.w3.org/XML/1998/namespace"[..])]
+    );
+}
+
+#[test]
+fn test_inherited_prefixes() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns="http://default.com" xmlns:ex="http://example.com"><a xmlns:ex="http://other.com"><b/></a></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let a = xoz.first_child(doc).unwrap();
+    let b = xoz.first_child(a).unwrap();
+
+    assert_eq!(xoz.inherited_prefixes(doc).count(), 0);
+    assert_eq!(
+        xoz.inherited_prefixes(a).collect::<Vec<_>>(),
+        vec![(&b""[..], &b"http://default.com"[..])]
+    );
+    assert_eq!(
+        xoz.inherited_prefixes(b).collect::<Vec<_>>(),
+        vec![
+            (&b"ex"[..], &b"http://other.com"[..]),
+            (&b""[..], &b"http://default.com"[..]),
+        ]
+    );
+}
+
+#[test]
+fn test_resolve_qname() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns="http://default.com" xmlns:ex="http://example.com"><a xmlns=""/></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let a = xoz.first_child(doc).unwrap();
+
+    assert_eq!(
+        xoz.resolve_qname(doc, " ex:thing "),
+        Ok(NodeName::new("http://example.com", "thing"))
+    );
+    assert_eq!(
+        xoz.resolve_qname(doc, "thing"),
+        Ok(NodeName::new("http://default.com", "thing"))
+    );
+    assert_eq!(xoz.resolve_qname(a, "thing"), Ok(NodeName::new("", "thing")));
+    assert_eq!(
+        xoz.resolve_qname(a, "xml:lang"),
+        Ok(NodeName::new("http://www.w3.org/XML/1998/namespace", "lang"))
+    );
+    assert_eq!(
+        xoz.resolve_qname(a, "no:thing"),
+        Err(QNameError::UndeclaredPrefix("no".to_string()))
+    );
+    for invalid in ["", "ex:", ":thing", "ex:a:b", "1thing", "a b"] {
+        assert_eq!(
+            xoz.resolve_qname(doc, invalid),
+            Err(QNameError::Invalid(invalid.to_string()))
+        );
+    }
+}
+
+#[test]
+fn test_attribute_qname() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns:xs="http://www.w3.org/2001/XMLSchema"><el type="xs:string" bad="zz:x"/></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let el = xoz.first_child(doc).unwrap();
+
+    assert_eq!(
+        xoz.attribute_qname(el, "type"),
+        Ok(Some(NodeName::new("http://www.w3.org/2001/XMLSchema", "string")))
+    );
+    assert_eq!(
+        xoz.attribute_qname(el, "bad"),
+        Err(QNameError::UndeclaredPrefix("zz".to_string()))
+    );
+    assert_eq!(xoz.attribute_qname(doc, "type"), Ok(None));
+}
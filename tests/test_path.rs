@@ -0,0 +1,153 @@
+use xoz::{NodeName, Xoz};
+
+#[test]
+fn test_path_positions() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str("<doc><a/><b/><a>one<!--c-->two<?pi x?></a></doc>")
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let a2 = xoz.last_child(doc).unwrap();
+    let one = xoz.first_child(a2).unwrap();
+    let comment = xoz.next_sibling(one).unwrap();
+    let two = xoz.next_sibling(comment).unwrap();
+    let pi = xoz.next_sibling(two).unwrap();
+
+    assert_eq!(xoz.path(doc), "/doc[1]");
+    assert_eq!(xoz.path(a2), "/doc[1]/a[2]");
+    assert_eq!(xoz.path(one), "/doc[1]/a[2]/text()[1]");
+    assert_eq!(xoz.path(comment), "/doc[1]/a[2]/comment()[1]");
+    assert_eq!(xoz.path(two), "/doc[1]/a[2]/text()[2]");
+    assert_eq!(xoz.path(pi), "/doc[1]/a[2]/processing-instruction()[1]");
+}
+
+#[test]
+fn test_path_positions_nested() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str("<doc><a><a/>x<b>y</b></a><b><a/></b>z<a/></doc>")
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let a1 = xoz.first_child(doc).unwrap();
+    let b = xoz.next_sibling(a1).unwrap();
+    let z = xoz.next_sibling(b).unwrap();
+    let a2 = xoz.next_sibling(z).unwrap();
+
+    assert_eq!(xoz.path(a1), "/doc[1]/a[1]");
+    assert_eq!(xoz.path(a2), "/doc[1]/a[2]");
+    assert_eq!(xoz.path(z), "/doc[1]/text()[1]");
+    assert_eq!(xoz.resolve_path(root, "/doc/a[2]"), Some(a2));
+}
+
+#[test]
+fn test_path_namespaces() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns:ex="http://example.com/ns"><ex:a ex:id="1"/><b xmlns="http://default.com/ns"><c/></b></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let a = xoz.first_child(doc).unwrap();
+    let b = xoz.next_sibling(a).unwrap();
+    let c = xoz.first_child(b).unwrap();
+    let id = xoz
+        .attribute_node(a, NodeName::new("http://example.com/ns", "id"))
+        .unwrap();
+
+    assert_eq!(xoz.path(a), "/doc[1]/ex:a[1]");
+    assert_eq!(xoz.path(id), "/doc[1]/ex:a[1]/@ex:id");
+    assert_eq!(
+        xoz.path(c),
+        "/doc[1]/Q{http://default.com/ns}b[1]/Q{http://default.com/ns}c[1]"
+    );
+    assert_eq!(
+        xoz.resolve_path(root, "/doc/Q{http://example.com/ns}a"),
+        Some(a)
+    );
+}
+
+#[test]
+fn test_resolve_path_round_trip() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns:ex="http://example.com"><p a="1">x<b/>y</p><ex:p xmlns="http://d.com"><ex:q ex:z="2" xmlns:ex="http://other.com"/></ex:p><p/></doc>"#,
+        )
+        .unwrap();
+    let nodes = xoz
+        .descendants_or_self(root)
+        .flat_map(|n| {
+            let mut nodes = vec![n];
+            nodes.extend(xoz.attributes(n));
+            if let Some(namespaces) = xoz.namespaces_child(n) {
+                nodes.extend(xoz.children(namespaces));
+            }
+            nodes
+        })
+        .collect::<Vec<_>>();
+    for node in nodes {
+        let path = xoz.path(node);
+        assert_eq!(xoz.resolve_path(root, &path), Some(node), "{}", path);
+    }
+}
+
+#[test]
+fn test_path_prefix_declared_on_element() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><ex:q xmlns:ex="A"/><ex:q xmlns:ex="B"/></doc>"#)
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let q1 = xoz.first_child(doc).unwrap();
+    let q2 = xoz.next_sibling(q1).unwrap();
+
+    assert_eq!(xoz.path(q1), "/doc[1]/Q{A}q[1]");
+    assert_eq!(xoz.path(q2), "/doc[1]/Q{B}q[1]");
+    assert_eq!(xoz.resolve_path(root, &xoz.path(q1)), Some(q1));
+    assert_eq!(xoz.resolve_path(root, &xoz.path(q2)), Some(q2));
+    // the prefix is only declared on the element itself
+    assert_eq!(xoz.resolve_path(root, "/doc/ex:q"), None);
+}
+
+#[test]
+fn test_path_rebound_prefix() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc xmlns:ex="A"><ex:q/><p xmlns:ex="B"><ex:q ex:id="1"/><x:q xmlns:x="A"/></p></doc>"#,
+        )
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let q1 = xoz.first_child(doc).unwrap();
+    let p = xoz.next_sibling(q1).unwrap();
+    let q2 = xoz.first_child(p).unwrap();
+    let q3 = xoz.next_sibling(q2).unwrap();
+    let id = xoz.attribute_node(q2, NodeName::new("B", "id")).unwrap();
+
+    assert_eq!(xoz.path(q1), "/doc[1]/ex:q[1]");
+    assert_eq!(xoz.path(q2), "/doc[1]/p[1]/ex:q[1]");
+    assert_eq!(xoz.path(id), "/doc[1]/p[1]/ex:q[1]/@ex:id");
+    // ex is declared for A further up, but rebound to B in p
+    assert_eq!(xoz.path(q3), "/doc[1]/p[1]/Q{A}q[1]");
+    for node in [q1, q2, q3, id] {
+        assert_eq!(xoz.resolve_path(root, &xoz.path(node)), Some(node));
+    }
+}
+
+#[test]
+fn test_resolve_path_invalid() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<doc><p/></doc>").unwrap();
+    let doc = xoz.document_element(root);
+    let p = xoz.first_child(doc).unwrap();
+
+    assert_eq!(xoz.resolve_path(p, "/"), Some(root));
+    assert_eq!(xoz.resolve_path(p, "/doc/p"), Some(p));
+    assert_eq!(xoz.resolve_path(root, "doc"), None);
+    assert_eq!(xoz.resolve_path(root, "/doc//p"), None);
+    assert_eq!(xoz.resolve_path(root, "/doc[0]"), None);
+    assert_eq!(xoz.resolve_path(root, "/doc[x]"), None);
+    assert_eq!(xoz.resolve_path(root, "/doc/un:p"), None);
+    assert_eq!(xoz.resolve_path(root, "/doc/Q{p"), None);
+}
@@ -0,0 +1,323 @@
+use regex::Regex;
+use xoz::{NodeType, TextStorage, WordQuery, Xoz};
+
+#[test]
+fn test_search_text_regex() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><a>foo {{x}} bar {{y}}</a><b>nothing</b><c>{{z}}</c></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let a = xoz.first_child(doc_el).unwrap();
+    let b = xoz.next_sibling(a).unwrap();
+    let c = xoz.next_sibling(b).unwrap();
+    let a_text = xoz.first_child(a).unwrap();
+    let c_text = xoz.first_child(c).unwrap();
+
+    let regex = Regex::new(r"\{\{\w+\}\}").unwrap();
+    let found = xoz.search_text_regex(root, &regex).collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![(a_text, 4..9), (a_text, 14..19), (c_text, 0..5)]
+    );
+
+    // only search under a node
+    let found = xoz.search_text_regex(c, &regex).collect::<Vec<_>>();
+    assert_eq!(found, vec![(c_text, 0..5)]);
+    let found = xoz.search_text_regex(b, &regex).collect::<Vec<_>>();
+    assert_eq!(found, vec![]);
+}
+
+#[test]
+fn test_search_text_regex_does_not_cross_nodes() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(r#"<doc><a>ab</a><b>cd</b></doc>"#).unwrap();
+    let regex = Regex::new(r"b.c").unwrap();
+    assert_eq!(xoz.search_text_regex(root, &regex).count(), 0);
+    // anchors apply to each text separately
+    let regex = Regex::new(r"^c").unwrap();
+    assert_eq!(xoz.search_text_regex(root, &regex).count(), 1);
+}
+
+#[test]
+fn test_search_text_regex_attributes() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc alt="TBD"><!--TBD--><?pi TBD?><p>TBD</p></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let alt = xoz.attribute_node(doc_el, "alt").unwrap();
+    let p = xoz
+        .typed_descendant(doc_el, xoz::NodeType::element("p"))
+        .unwrap();
+    let p_text = xoz.first_child(p).unwrap();
+
+    // comments and processing instructions aren't searched
+    let regex = Regex::new("TBD").unwrap();
+    let found = xoz.search_text_regex(root, &regex).collect::<Vec<_>>();
+    assert_eq!(found, vec![(alt, 0..3), (p_text, 0..3)]);
+}
+
+#[test]
+fn test_search_text_regex_storage() {
+    let regex = Regex::new(r"x\d").unwrap();
+    for storage in [
+        TextStorage::Plain,
+        TextStorage::Compressed,
+        TextStorage::Deduplicated,
+    ] {
+        let mut xoz = Xoz::with_text_storage(storage);
+        let root = xoz
+            .parse_str(r#"<doc><a>x1</a><a>x2</a><a>x1</a></doc>"#)
+            .unwrap();
+        let found = xoz
+            .search_text_regex(root, &regex)
+            .map(|(node, range)| xoz.node_text(node).unwrap()[range].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec!["x1", "x2", "x1"]);
+    }
+}
+
+fn is_block(node_type: &NodeType) -> bool {
+    [NodeType::element("p"), NodeType::element("title")].contains(node_type)
+}
+
+#[test]
+fn test_search_words() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc><p title="fox">The quick <!--fox--><em>brown</em> fox</p><p>A Fox!</p></doc>"#,
+        )
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let p1 = xoz.first_child(doc_el).unwrap();
+    let p2 = xoz.next_sibling(p1).unwrap();
+    let text1 = xoz.first_child(p1).unwrap();
+    let p2_text = xoz.first_child(p2).unwrap();
+
+    // attributes and comments aren't searched
+    let found = xoz.search_words(root, &WordQuery::term("FOX"), is_block);
+    let nodes = found.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(found[1], (p2_text, 2));
+    assert!(xoz.is_ancestor(p1, nodes[0]));
+
+    // a phrase continues past inline markup
+    let found = xoz.search_words(root, &WordQuery::phrase("quick brown fox"), is_block);
+    assert_eq!(found, vec![(text1, 4)]);
+    // but must end under the node
+    let em = xoz.next_sibling(xoz.next_sibling(text1).unwrap()).unwrap();
+    assert_eq!(
+        xoz.search_words(em, &WordQuery::phrase("brown fox"), is_block),
+        vec![]
+    );
+
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::near("the", "fox", 3), is_block),
+        vec![(text1, 0)]
+    );
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::near("the", "fox", 2), is_block),
+        vec![]
+    );
+}
+
+#[test]
+fn test_search_words_in() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(concat!(
+            "<doc><section><title>Start</title><section><p>end here</p></section></section>",
+            "<section><p>start end</p><p>start</p></section></doc>"
+        ))
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let section1 = xoz.first_child(doc_el).unwrap();
+    let title = xoz.first_child(section1).unwrap();
+    let section2 = xoz.next_sibling(title).unwrap();
+    let section3 = xoz.next_sibling(section1).unwrap();
+    let section = NodeType::element("section");
+
+    assert_eq!(
+        xoz.search_words_in(root, &WordQuery::term("start"), section.clone(), is_block),
+        vec![section1, section3]
+    );
+    assert_eq!(
+        xoz.search_words_in(root, &WordQuery::term("end"), section.clone(), is_block),
+        vec![section2, section3]
+    );
+    // a phrase doesn't go on from the title into the next paragraph
+    assert_eq!(
+        xoz.search_words_in(
+            root,
+            &WordQuery::phrase("start end"),
+            section.clone(),
+            is_block
+        ),
+        vec![section3]
+    );
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::near("start", "end", 1), is_block),
+        vec![(
+            xoz.first_child(xoz.first_child(section3).unwrap()).unwrap(),
+            0
+        )]
+    );
+    // only under the node
+    assert_eq!(
+        xoz.search_words_in(
+            section3,
+            &WordQuery::term("start"),
+            section.clone(),
+            is_block
+        ),
+        vec![section3]
+    );
+    assert_eq!(
+        xoz.search_words_in(
+            section1,
+            &WordQuery::term("start"),
+            NodeType::element("p"),
+            is_block
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn test_search_words_compressed() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    let root = xoz
+        .parse_str("<doc><p>one two</p><p>two three</p></doc>")
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let p2 = xoz.last_child(doc_el).unwrap();
+    let text2 = xoz.first_child(p2).unwrap();
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::phrase("two three"), is_block),
+        vec![(text2, 0)]
+    );
+}
+
+#[test]
+fn test_search_words_sibling_inline_elements() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str("<doc><p><em>quick</em><strong>fox</strong></p><p>jumps</p></doc>")
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let p1 = xoz.first_child(doc_el).unwrap();
+    let quick = xoz.first_child(xoz.first_child(p1).unwrap()).unwrap();
+
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::phrase("quick fox"), is_block),
+        vec![(quick, 0)]
+    );
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::phrase("fox jumps"), is_block),
+        vec![]
+    );
+    // without blocks the whole document is one
+    assert_eq!(
+        xoz.search_words(root, &WordQuery::phrase("fox jumps"), |_| false)
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_snippet_regex_hits() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(concat!(
+            "<doc><title>Intro</title>",
+            "<p>Before <b>bold</b> the <!--no-->needle and <i>after</i> it</p>",
+            "<p>Next paragraph</p></doc>"
+        ))
+        .unwrap();
+    let regex = Regex::new("needle").unwrap();
+    let (node, range) = xoz.search_text_regex(root, &regex).next().unwrap();
+    let is_block = |node_type: &NodeType| {
+        node_type == &NodeType::element("p") || node_type == &NodeType::element("title")
+    };
+
+    let snippet = xoz
+        .snippet(node, range.clone(), 100, 100, is_block)
+        .unwrap();
+    assert_eq!(snippet.node(), node);
+    assert_eq!(snippet.range(), range);
+    assert_eq!(snippet.before(), "Before bold the ");
+    assert_eq!(snippet.matched(), "needle");
+    assert_eq!(snippet.after(), " and after it");
+
+    let snippet = xoz.snippet(node, range.clone(), 8, 7, is_block).unwrap();
+    assert_eq!(snippet.before(), "old the ");
+    assert_eq!(snippet.after(), " and af");
+
+    // without blocks the context comes from the whole document
+    let snippet = xoz.snippet(node, range, 20, 100, |_| false).unwrap();
+    assert_eq!(snippet.before(), "ntroBefore bold the ");
+    assert_eq!(snippet.after(), " and after itNext paragraph");
+}
+
+#[test]
+fn test_snippet_attribute() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><p alt="a long text with needle in the middle">text</p></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let p = xoz.first_child(doc_el).unwrap();
+    let alt = xoz.attribute_node(p, "alt").unwrap();
+    let regex = Regex::new("needle").unwrap();
+    let (node, range) = xoz.search_text_regex(root, &regex).next().unwrap();
+    assert_eq!(node, alt);
+    let snippet = xoz.snippet(node, range, 5, 50, |_| false).unwrap();
+    assert_eq!(snippet.before(), "with ");
+    assert_eq!(snippet.after(), " in the middle");
+    assert_eq!(snippet.path(), &[root, doc_el, p, alt]);
+}
+
+#[test]
+fn test_snippet_multibyte_context() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    let root = xoz.parse_str("<p>ééé <b>ü</b>x ñññ</p>").unwrap();
+    let p = xoz.document_element(root);
+    let regex = Regex::new("x").unwrap();
+    let (node, range) = xoz.search_text_regex(root, &regex).next().unwrap();
+    let snippet = xoz.snippet(node, range, 3, 2, |_| false).unwrap();
+    assert_eq!(snippet.before(), "é ü");
+    assert_eq!(snippet.after(), " ñ");
+    assert_eq!(snippet.path()[1], p);
+}
+
+#[test]
+fn test_snippet_without_text() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str("<doc><p>needle</p><p>other</p></doc>")
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let p = xoz.first_child(doc_el).unwrap();
+    // elements have no text of their own, so there is no snippet
+    assert!(xoz.snippet(p, 0..6, 10, 10, |_| false).is_none());
+    assert!(xoz.snippet(root, 0..0, 10, 10, |_| false).is_none());
+}
+
+#[test]
+fn test_snippet_bad_range() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<p>één needle</p>").unwrap();
+    let p = xoz.document_element(root);
+    let text = xoz.first_child(p).unwrap();
+    // out of bounds
+    assert!(xoz.snippet(text, 5..100, 10, 10, |_| false).is_none());
+    assert!(xoz.snippet(text, 100..101, 10, 10, |_| false).is_none());
+    // not on a char boundary, as é takes two bytes
+    assert!(xoz.snippet(text, 1..3, 10, 10, |_| false).is_none());
+    assert!(xoz.snippet(text, 0..1, 10, 10, |_| false).is_none());
+    let snippet = xoz.snippet(text, 0..2, 10, 10, |_| false).unwrap();
+    assert_eq!(snippet.matched(), "é");
+    assert_eq!(snippet.after(), "én needle");
+}
@@ -0,0 +1,210 @@
+use xoz::{error::XPathError, Xoz};
+
+fn names(xoz: &Xoz, expr: &str, context: xoz::Node) -> Vec<String> {
+    xoz.select(context, expr)
+        .unwrap()
+        .iter()
+        .map(|n| xoz.string_value(n))
+        .collect()
+}
+
+#[test]
+fn test_select_descendant_child() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            "<book><chapter><title>A</title><section><title>A.1</title></section></chapter><chapter><title>B</title></chapter></book>",
+        )
+        .unwrap();
+    assert_eq!(names(&xoz, "//chapter/title", root), vec!["A", "B"]);
+    assert_eq!(names(&xoz, "//title", root), vec!["A", "A.1", "B"]);
+    assert_eq!(
+        names(&xoz, "/book/chapter/section/title", root),
+        vec!["A.1"]
+    );
+    assert_eq!(names(&xoz, "/book/title", root), Vec::<String>::new());
+}
+
+#[test]
+fn test_select_relative() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str("<a><b><c>1</c></b><b><c>2</c></b><c>3</c></a>")
+        .unwrap();
+    let a = xoz.document_element(root);
+    let b1 = xoz.first_child(a).unwrap();
+    assert_eq!(names(&xoz, "b/c", a), vec!["1", "2"]);
+    assert_eq!(names(&xoz, "c", b1), vec!["1"]);
+    // relative paths don't go outside of the context node
+    assert_eq!(names(&xoz, "descendant::c", b1), vec!["1"]);
+    // absolute paths start at the root
+    assert_eq!(names(&xoz, "//c", b1), vec!["1", "2", "3"]);
+    assert_eq!(
+        xoz.select(b1, "/").unwrap().iter().collect::<Vec<_>>(),
+        vec![root]
+    );
+}
+
+#[test]
+fn test_select_union_document_order() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<a><b>1</b><c>2</c><b>3</b></a>").unwrap();
+    assert_eq!(names(&xoz, "//c | //b", root), vec!["1", "2", "3"]);
+    assert_eq!(names(&xoz, "//b | //b", root), vec!["1", "3"]);
+}
+
+#[test]
+fn test_select_namespaces() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc xmlns:ex="http://example.com"><ex:p>1</ex:p><p>2</p></doc>"#)
+        .unwrap();
+    assert_eq!(names(&xoz, "//ex:p", root), vec!["1"]);
+    assert_eq!(names(&xoz, "//p", root), vec!["2"]);
+    assert_eq!(
+        xoz.select(root, "//other:p"),
+        Err(XPathError::UndeclaredPrefix("other".to_string()))
+    );
+}
+
+#[test]
+fn test_select_wildcards() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc xmlns:ex="http://example.com"><ex:p ex:n="a" n="b">1</ex:p><q>2</q><ex:r>3</ex:r></doc>"#)
+        .unwrap();
+    assert_eq!(names(&xoz, "/doc/*", root), vec!["1", "2", "3"]);
+    assert_eq!(names(&xoz, "//ex:*", root), vec!["1", "3"]);
+    assert_eq!(names(&xoz, "//@*", root), vec!["a", "b"]);
+    assert_eq!(names(&xoz, "//@ex:*", root), vec!["a"]);
+    assert_eq!(names(&xoz, "//*[@ex:*]", root), vec!["1"]);
+    assert!(matches!(
+        xoz.select(root, "/doc/namespace::ex"),
+        Err(XPathError::Unsupported(_))
+    ));
+    // a would have x in scope, but has no namespace node for it
+    let root = xoz.parse_str(r#"<doc xmlns:x="urn:x"><a/></doc>"#).unwrap();
+    assert!(matches!(
+        xoz.select(root, "//namespace::*"),
+        Err(XPathError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_select_kind_tests() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str("<doc>a<!--b--><?x c?><?y d?><p>e</p></doc>")
+        .unwrap();
+    // the string value of a processing instruction starts after its target
+    let trimmed = |expr| {
+        names(&xoz, expr, root)
+            .iter()
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&xoz, "//text()", root), vec!["a", "e"]);
+    assert_eq!(names(&xoz, "/doc/comment()", root), vec!["b"]);
+    assert_eq!(trimmed("//processing-instruction()"), vec!["c", "d"]);
+    assert_eq!(trimmed("//processing-instruction('y')"), vec!["d"]);
+    assert_eq!(
+        trimmed("//p/preceding-sibling::processing-instruction('x')"),
+        vec!["c"]
+    );
+    assert_eq!(names(&xoz, "/doc/node()", root).len(), 5);
+}
+
+#[test]
+fn test_select_many_siblings() {
+    let mut xml = String::from("<doc>");
+    for _ in 0..100_000 {
+        xml.push_str("<p/>");
+    }
+    xml.push_str("<figure>F</figure></doc>");
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(&xml).unwrap();
+    let doc = xoz.document_element(root);
+    assert_eq!(xoz.select(doc, "p").unwrap().len(), 100_000);
+    let first = xoz.first_child(doc).unwrap();
+    assert_eq!(names(&xoz, "following-sibling::figure", first), vec!["F"]);
+    assert_eq!(names(&xoz, "/doc/figure", root), vec!["F"]);
+}
+
+#[test]
+fn test_select_errors() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<a/>").unwrap();
+    assert!(matches!(
+        xoz.select(root, "//a["),
+        Err(XPathError::Syntax(_, _))
+    ));
+    assert!(matches!(
+        xoz.select(root, "//a[1]"),
+        Err(XPathError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_select_axes() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><sec id="1"><p>a</p><p>b</p></sec><sec id="2"><p>c</p></sec></doc>"#)
+        .unwrap();
+    let doc = xoz.document_element(root);
+    let sec1 = xoz.first_child(doc).unwrap();
+    let sec2 = xoz.next_sibling(sec1).unwrap();
+    let p1 = xoz.first_child(sec1).unwrap();
+
+    assert_eq!(names(&xoz, "//sec/@id", root), vec!["1", "2"]);
+    assert_eq!(names(&xoz, "//p/../@id", root), vec!["1", "2"]);
+    assert_eq!(names(&xoz, "following-sibling::p", p1), vec!["b"]);
+    assert_eq!(names(&xoz, "following::p", p1), vec!["b", "c"]);
+    assert_eq!(names(&xoz, "preceding::p", sec2), vec!["a", "b"]);
+    assert_eq!(names(&xoz, "ancestor::sec/@id", p1), vec!["1"]);
+    assert_eq!(
+        names(&xoz, "preceding-sibling::sec/p", sec2),
+        vec!["a", "b"]
+    );
+    assert_eq!(names(&xoz, "descendant-or-self::sec/p", sec2), vec!["c"]);
+    assert_eq!(names(&xoz, "self::sec/@id", sec2), vec!["2"]);
+    assert_eq!(
+        xoz.select(root, "//p/ancestor-or-self::node()")
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![
+            root,
+            doc,
+            sec1,
+            p1,
+            xoz.next_sibling(p1).unwrap(),
+            sec2,
+            xoz.first_child(sec2).unwrap()
+        ]
+    );
+}
+
+#[test]
+fn test_select_predicates() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(
+            r#"<doc><p type="note">a<em>!</em></p><p>b</p><p type="warning">c</p><p>d<em/></p></doc>"#,
+        )
+        .unwrap();
+    assert_eq!(names(&xoz, "//p[@type='note']", root), vec!["a!"]);
+    assert_eq!(names(&xoz, "//p[@type and not(em)]", root), vec!["c"]);
+    assert_eq!(names(&xoz, "//p[em or .='b']", root), vec!["a!", "b", "d"]);
+    assert_eq!(
+        names(&xoz, "//p[.='d']/preceding-sibling::p[not(@type)]", root),
+        vec!["b"]
+    );
+    assert_eq!(
+        names(&xoz, "/doc[p/@type='warning']/p[em='!']", root),
+        vec!["a!"]
+    );
+    assert!(matches!(
+        xoz.select(root, "//p[../p]"),
+        Err(XPathError::Unsupported(_))
+    ));
+}
@@ -1,4 +1,4 @@
-use xoz::Xoz;
+use xoz::{TextStorage, Xoz};
 
 #[test]
 fn test_text() {
@@ -23,6 +23,21 @@ fn test_multiple_text() {
     assert_eq!(xoz.text_str(b_text), Some("B"));
 }
 
+#[test]
+fn test_text_after_comment() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc><!--comment--><?pi data?>text</doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let comment = xoz.first_child(doc_el).unwrap();
+    let pi = xoz.next_sibling(comment).unwrap();
+    let text = xoz.next_sibling(pi).unwrap();
+    assert_eq!(xoz.comment_str(comment), Some("comment"));
+    assert_eq!(xoz.processing_instruction_str(pi), Some("pi data"));
+    assert_eq!(xoz.text_str(text), Some("text"));
+}
+
 #[test]
 fn test_processing_instruction() {
     let mut xoz = Xoz::new();
@@ -34,3 +49,232 @@ fn test_processing_instruction() {
     assert_eq!(pi.target(), b"target");
     assert_eq!(pi.content(), b" content");
 }
+
+#[test]
+fn test_compressed_text() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    let root = xoz
+        .parse_str(r#"<doc a="A"><a>A</a><b>B</b><!--comment--></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let a = xoz.first_child(doc_el).unwrap();
+    let a_text = xoz.first_child(a).unwrap();
+    let a_attribute = xoz.attribute_node(doc_el, "a").unwrap();
+    assert_eq!(xoz.node_text(a_text).as_deref(), Some("A"));
+    assert_eq!(xoz.node_text(a_attribute).as_deref(), Some("A"));
+    assert_eq!(xoz.attribute_text(doc_el, "a").as_deref(), Some("A"));
+    assert_eq!(
+        xoz.serialize_to_string(root),
+        r#"<doc a="A"><a>A</a><b>B</b><!--comment--></doc>"#
+    );
+}
+
+#[test]
+#[should_panic(expected = "Compressed text cannot be borrowed")]
+fn test_compressed_text_str() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Compressed);
+    let root = xoz.parse_str("<doc>text</doc>").unwrap();
+    let text = xoz.first_child(xoz.document_element(root)).unwrap();
+    xoz.text_str(text);
+}
+
+#[test]
+fn test_compressed_text_heap_size() {
+    let mut xml = String::from("<doc>");
+    for i in 0..10_000 {
+        xml.push_str(&format!(
+            "<p>Paragraph {} with some prose that repeats.</p>",
+            i
+        ));
+    }
+    xml.push_str("</doc>");
+
+    let mut plain = Xoz::new();
+    let plain_root = plain.parse_str(&xml).unwrap();
+    let mut compressed = Xoz::with_text_storage(TextStorage::Compressed);
+    let root = compressed.parse_str(&xml).unwrap();
+    assert!(compressed.heap_size() < plain.heap_size());
+
+    // accessing text decompresses blocks, clearing the cache drops them again
+    let before = compressed.heap_size();
+    assert_eq!(
+        compressed.string_value(root),
+        plain.string_value(plain_root)
+    );
+    assert!(compressed.heap_size() > before);
+    // only a few blocks are kept decompressed, even after going through all
+    // of the text
+    assert!(compressed.heap_size() < plain.heap_size());
+    compressed.clear_text_cache();
+    assert_eq!(compressed.heap_size(), before);
+}
+
+#[test]
+fn test_deduplicated_text() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Deduplicated);
+    let root = xoz
+        .parse_str(r#"<doc><a t="true">true</a><b t="false">true</b></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let a = xoz.first_child(doc_el).unwrap();
+    let b = xoz.next_sibling(a).unwrap();
+    let a_text = xoz.first_child(a).unwrap();
+    let b_text = xoz.first_child(b).unwrap();
+    let a_t = xoz.attribute_node(a, "t").unwrap();
+    let b_t = xoz.attribute_node(b, "t").unwrap();
+
+    assert_eq!(xoz.text_str(a_text), Some("true"));
+    assert_eq!(xoz.text_str(b_text), Some("true"));
+    assert_eq!(xoz.attribute_str(a_t), Some("true"));
+    assert_eq!(xoz.attribute_str(b_t), Some("false"));
+
+    assert_eq!(xoz.value_id(a_text), xoz.value_id(b_text));
+    assert_eq!(xoz.value_id(a_text), xoz.value_id(a_t));
+    assert_ne!(xoz.value_id(a_t), xoz.value_id(b_t));
+
+    assert_eq!(
+        xoz.serialize_to_string(root),
+        r#"<doc><a t="true">true</a><b t="false">true</b></doc>"#
+    );
+}
+
+#[test]
+fn test_deduplicated_value_id_across_documents() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Deduplicated);
+    let root1 = xoz.parse_str(r#"<doc>same</doc>"#).unwrap();
+    let root2 = xoz.parse_str(r#"<doc>same</doc>"#).unwrap();
+    let text1 = xoz.first_child(xoz.document_element(root1)).unwrap();
+    let text2 = xoz.first_child(xoz.document_element(root2)).unwrap();
+    assert_ne!(xoz.value_id(text1), xoz.value_id(text2));
+}
+
+#[test]
+fn test_plain_text_has_no_value_id() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(r#"<doc>text</doc>"#).unwrap();
+    let text = xoz.first_child(xoz.document_element(root)).unwrap();
+    assert_eq!(xoz.value_id(text), None);
+}
+
+#[test]
+fn test_string_value() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<p xmlns:x="http://example.com" a="A">one<!--c--><?t d?><b x:c="C">two</b>three</p>"#)
+        .unwrap();
+    let p = xoz.document_element(root);
+    let text = xoz.first_child(p).unwrap();
+    let comment = xoz.next_sibling(text).unwrap();
+    let pi = xoz.next_sibling(comment).unwrap();
+    let b = xoz.next_sibling(pi).unwrap();
+    let a = xoz.attribute_node(p, "a").unwrap();
+
+    assert_eq!(xoz.string_value(root), "onetwothree");
+    assert_eq!(xoz.string_value(p), "onetwothree");
+    assert_eq!(xoz.string_value(b), "two");
+    assert_eq!(xoz.string_value(text), "one");
+    assert_eq!(xoz.string_value(comment), "c");
+    assert_eq!(xoz.string_value(pi), " d");
+    assert_eq!(xoz.string_value(a), "A");
+    assert_eq!(
+        xoz.string_value_parts(p).collect::<Vec<_>>(),
+        vec!["one", "two", "three"]
+    );
+}
+
+#[test]
+fn test_string_value_without_text() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<p xmlns="http://example.com"><a/></p>"#)
+        .unwrap();
+    let p = xoz.document_element(root);
+    assert_eq!(xoz.string_value_parts(p).count(), 0);
+    assert_eq!(xoz.string_value(root), "");
+}
+
+#[test]
+fn test_string_value_parts_deduplicated() {
+    let mut xoz = Xoz::with_text_storage(TextStorage::Deduplicated);
+    let root = xoz
+        .parse_str("<p><a>x</a><b>y</b><a>x</a><!--x--></p>")
+        .unwrap();
+    let p = xoz.document_element(root);
+    assert_eq!(
+        xoz.string_value_parts(p).collect::<Vec<_>>(),
+        vec!["x", "y", "x"]
+    );
+    let mut s = String::new();
+    xoz.write_string_value(p, &mut s).unwrap();
+    assert_eq!(s, "xyx");
+}
+
+#[test]
+fn test_text_offsets_roundtrip() {
+    for text_storage in [
+        TextStorage::Plain,
+        TextStorage::Compressed,
+        TextStorage::Deduplicated,
+    ] {
+        let mut xoz = Xoz::with_text_storage(text_storage);
+        let root = xoz
+            .parse_str(r#"<doc><p a="attr">één <!--c-->twee</p><?pi x?><p>één</p></doc>"#)
+            .unwrap();
+        let plain = xoz.string_value(root);
+        assert_eq!(plain, "één tweeéén");
+        let text_nodes = xoz
+            .descendants(root)
+            .filter(|n| xoz.is_text(*n))
+            .collect::<Vec<_>>();
+        for offset in 0..=plain.len() {
+            let (node, node_offset) = xoz.text_offset_node(root, offset).unwrap();
+            assert!(text_nodes.contains(&node));
+            assert_eq!(xoz.text_offset(node, node_offset), Some(offset));
+        }
+        assert_eq!(xoz.text_offset_node(root, plain.len() + 1), None);
+
+        let doc_el = xoz.document_element(root);
+        let p2 = xoz.last_child(doc_el).unwrap();
+        assert_eq!(xoz.text_offset(p2, 0), Some(plain.len() - "één".len()));
+        let attr = xoz
+            .attribute_node(xoz.first_child(doc_el).unwrap(), "a")
+            .unwrap();
+        assert_eq!(xoz.text_offset(attr, 0), None);
+    }
+}
+
+#[test]
+fn test_text_offsets_skip_attributes() {
+    let mut xoz = Xoz::new();
+    let root = xoz
+        .parse_str(r#"<doc id="d"><a id="1"><b>t1</b></a><a id="2"><b>t2</b></a></doc>"#)
+        .unwrap();
+    let doc_el = xoz.document_element(root);
+    let a1 = xoz.first_child(doc_el).unwrap();
+    let t1 = xoz.first_child(xoz.first_child(a1).unwrap()).unwrap();
+    let a2 = xoz.next_sibling(a1).unwrap();
+    let t2 = xoz.first_child(xoz.first_child(a2).unwrap()).unwrap();
+    assert_eq!(xoz.text_offset(t1, 0), Some(0));
+    assert_eq!(xoz.text_offset(t2, 0), Some(2));
+    assert_eq!(xoz.text_offset(a2, 1), Some(3));
+    assert_eq!(xoz.text_offset_node(root, 3), Some((t2, 1)));
+    // the document is given by its root
+    assert_eq!(xoz.text_offset_node(doc_el, 3), None);
+}
+
+#[test]
+fn test_text_offsets_without_text() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str("<p><a/></p>").unwrap();
+    assert_eq!(xoz.text_offset_node(root, 0), None);
+    assert_eq!(xoz.text_offset(root, 0), Some(0));
+}
+
+#[test]
+fn test_serialize_non_last_child() {
+    let mut xoz = Xoz::new();
+    let root = xoz.parse_str(r#"<doc><a>A</a><b>B</b></doc>"#).unwrap();
+    let doc_el = xoz.document_element(root);
+    let a = xoz.first_child(doc_el).unwrap();
+    assert_eq!(xoz.serialize_to_string(a), r#"<a>A</a>"#);
+}